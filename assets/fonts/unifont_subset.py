@@ -0,0 +1,350 @@
+#!/usr/bin/env python3
+"""把 GNU Unifont 的 .hex 点阵转换成只含 GB2312 字符的 TrueType 字体
+
+    python3 unifont_subset.py unifont-13.0.06.hex UnifontCJKSubset.ttf
+
+每个像素是 64x64 字体单位的方块，相邻像素合并成一个轮廓，不做平滑。
+只用标准库，输出对同一份 .hex 是确定的。
+"""
+
+import struct
+import sys
+
+PIXEL = 64
+UNITS_PER_EM = 16 * PIXEL
+ASCENT = 14 * PIXEL
+DESCENT = -2 * PIXEL
+
+FAMILY = "Unifont CJK Subset"
+POSTSCRIPT = "UnifontCJKSubset-Regular"
+VERSION = "Version 13.0.06"
+COPYRIGHT = (
+    "Copyright (C) 1998-2020 Roman Czyborra, Paul Hardy, Qianqian Fang, "
+    "Andrew Miller, Johnnie Weaver, David Corbett, Nils Moskopp, "
+    "Rebecca Bettencourt, et al."
+)
+LICENSE = "This Font Software is licensed under the SIL Open Font License, Version 1.1."
+LICENSE_URL = "http://scripts.sil.org/OFL"
+
+
+def gb2312_chars():
+    chars = set()
+    for row in range(0xA1, 0xF8):
+        for col in range(0xA1, 0xFF):
+            try:
+                chars.add(ord(bytes([row, col]).decode("gb2312")))
+            except UnicodeDecodeError:
+                pass
+    # 全角空格也算进去，排版时 CJK 文本里常见
+    chars.add(0x3000)
+    return sorted(chars)
+
+
+def read_hex(path, wanted):
+    glyphs = {}
+    with open(path) as f:
+        for line in f:
+            code, bits = line.strip().split(":")
+            code = int(code, 16)
+            if code in wanted:
+                width = len(bits) // 4
+                rows = [int(bits[i * width // 4:(i + 1) * width // 4], 16) for i in range(16)]
+                glyphs[code] = (width, rows)
+    return glyphs
+
+
+def contours(width, rows):
+    """像素并集的边界，外轮廓顺时针、内轮廓逆时针（y 轴向上）"""
+    filled = {
+        (x, 15 - y)
+        for y, row in enumerate(rows)
+        for x in range(width)
+        if row >> (width - 1 - x) & 1
+    }
+
+    # 每个像素四条有向边，相邻像素的公共边方向相反，互相抵消
+    edges = {}
+    for x, y in filled:
+        for start, end in (
+            ((x, y), (x, y + 1)),
+            ((x, y + 1), (x + 1, y + 1)),
+            ((x + 1, y + 1), (x + 1, y)),
+            ((x + 1, y), (x, y)),
+        ):
+            if edges.get(end) and start in edges[end]:
+                edges[end].remove(start)
+            else:
+                edges.setdefault(start, []).append(end)
+
+    result = []
+    while True:
+        start = next((p for p in sorted(edges) if edges[p]), None)
+        if start is None:
+            return result
+        loop = [start]
+        previous, current = None, start
+        while True:
+            options = edges[current]
+            if len(options) > 1 and previous is not None:
+                # 对角相接的两个像素分成两个轮廓：总是优先右转
+                dx, dy = current[0] - previous[0], current[1] - previous[1]
+                right = (current[0] + dy, current[1] - dx)
+                end = right if right in options else options[0]
+            else:
+                end = options[0]
+            options.remove(end)
+            if end == start:
+                break
+            loop.append(end)
+            previous, current = current, end
+
+        # 去掉共线的点
+        points = []
+        for i, p in enumerate(loop):
+            a, b = loop[i - 1], loop[(i + 1) % len(loop)]
+            if (p[0] - a[0]) * (b[1] - p[1]) != (p[1] - a[1]) * (b[0] - p[0]):
+                points.append(p)
+        result.append(points)
+
+
+def encode_glyph(loops):
+    if not loops:
+        return b"", (0, 0, 0, 0), 0
+    points = [(x * PIXEL, y * PIXEL + DESCENT) for loop in loops for x, y in loop]
+    xs, ys = [p[0] for p in points], [p[1] for p in points]
+    bbox = (min(xs), min(ys), max(xs), max(ys))
+
+    ends, total = [], 0
+    for loop in loops:
+        total += len(loop)
+        ends.append(total - 1)
+
+    flags, xdata, ydata = bytearray(), bytearray(), bytearray()
+    last = (0, 0)
+    for p in points:
+        dx, dy = p[0] - last[0], p[1] - last[1]
+        flag = 0x01
+        if dx == 0:
+            flag |= 0x10
+        elif abs(dx) < 256:
+            flag |= 0x02 | (0x10 if dx > 0 else 0)
+            xdata.append(abs(dx))
+        else:
+            xdata += struct.pack(">h", dx)
+        if dy == 0:
+            flag |= 0x20
+        elif abs(dy) < 256:
+            flag |= 0x04 | (0x20 if dy > 0 else 0)
+            ydata.append(abs(dy))
+        else:
+            ydata += struct.pack(">h", dy)
+        flags.append(flag)
+        last = p
+
+    data = struct.pack(">h4h", len(loops), *bbox)
+    data += struct.pack(">%dH" % len(ends), *ends)
+    data += struct.pack(">H", 0) + flags + xdata + ydata
+    data += b"\0" * (-len(data) % 4)
+    return data, bbox, len(points)
+
+
+def cmap_table(mapping):
+    codes = sorted(mapping)
+    segments = []
+    for code in codes:
+        if segments and code == segments[-1][1] + 1 and mapping[code] == mapping[segments[-1][1]] + 1:
+            segments[-1][1] = code
+        else:
+            segments.append([code, code])
+    segments.append([0xFFFF, 0xFFFF])
+
+    count = len(segments)
+    search = 2 ** (count.bit_length() - 1)
+    body = struct.pack(
+        ">HHHH", count * 2, search * 2, search.bit_length() - 1, count * 2 - search * 2
+    )
+    body += b"".join(struct.pack(">H", end) for _, end in segments)
+    body += struct.pack(">H", 0)
+    body += b"".join(struct.pack(">H", start) for start, _ in segments)
+    for start, _ in segments:
+        delta = 1 if start == 0xFFFF else mapping[start] - start
+        body += struct.pack(">h", (delta + 0x8000) % 0x10000 - 0x8000)
+    body += struct.pack(">%dH" % count, *([0] * count))
+    subtable = struct.pack(">HHH", 4, 6 + len(body), 0) + body
+    return struct.pack(">HHHHI", 0, 1, 3, 1, 12) + subtable
+
+
+def name_table():
+    records = [
+        (0, COPYRIGHT),
+        (1, FAMILY),
+        (2, "Regular"),
+        (3, POSTSCRIPT),
+        (4, FAMILY),
+        (5, VERSION),
+        (6, POSTSCRIPT),
+        (13, LICENSE),
+        (14, LICENSE_URL),
+    ]
+    strings, entries = b"", b""
+    for name_id, text in records:
+        data = text.encode("utf-16-be")
+        entries += struct.pack(">6H", 3, 1, 0x409, name_id, len(data), len(strings))
+        strings += data
+    return struct.pack(">HHH", 0, len(records), 6 + 12 * len(records)) + entries + strings
+
+
+def checksum(data):
+    data += b"\0" * (-len(data) % 4)
+    return sum(struct.unpack(">%dI" % (len(data) // 4), data)) & 0xFFFFFFFF
+
+
+def build(hex_path, out_path):
+    chars = gb2312_chars()
+    bitmaps = read_hex(hex_path, set(chars))
+    chars = [c for c in chars if c in bitmaps]
+
+    glyf, loca, hmtx = b"", [0], b""
+    mapping = {}
+    max_points = max_contours = 0
+    x_min = y_min = x_max = y_max = 0
+    advance_max = 0
+    # 0 号字形 .notdef 留空
+    hmtx += struct.pack(">Hh", UNITS_PER_EM, 0)
+    loca.append(0)
+    for index, code in enumerate(chars, start=1):
+        width, rows = bitmaps[code]
+        loops = contours(width, rows)
+        data, bbox, points = encode_glyph(loops)
+        glyf += data
+        loca.append(len(glyf))
+        advance = width * PIXEL
+        advance_max = max(advance_max, advance)
+        hmtx += struct.pack(">Hh", advance, bbox[0])
+        mapping[code] = index
+        max_points = max(max_points, points)
+        max_contours = max(max_contours, len(loops))
+        if data:
+            x_min, y_min = min(x_min, bbox[0]), min(y_min, bbox[1])
+            x_max, y_max = max(x_max, bbox[2]), max(y_max, bbox[3])
+
+    glyph_count = len(chars) + 1
+    tables = {
+        b"cmap": cmap_table(mapping),
+        b"glyf": glyf,
+        b"head": struct.pack(
+            ">IIIIHHQQhhhhHHhhh",
+            0x00010000,
+            0x000D0006,
+            0,
+            0x5F0F3CF5,
+            0x000B,
+            UNITS_PER_EM,
+            0,
+            0,
+            x_min,
+            y_min,
+            x_max,
+            y_max,
+            0,
+            8,
+            2,
+            1,
+            0,
+        ),
+        b"hhea": struct.pack(
+            ">IhhhH11hH",
+            0x00010000,
+            ASCENT,
+            DESCENT,
+            0,
+            advance_max,
+            x_min,
+            0,
+            x_max,
+            1,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            0,
+            glyph_count,
+        ),
+        b"hmtx": hmtx,
+        b"loca": struct.pack(">%dI" % len(loca), *loca),
+        b"maxp": struct.pack(
+            ">I14H", 0x00010000, glyph_count, max_points, max_contours,
+            0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 0,
+        ),
+        b"name": name_table(),
+        b"OS/2": struct.pack(
+            ">HhHHH11h10B4I4sHHHhhhHHII2h3H",
+            4,
+            UNITS_PER_EM,
+            400,
+            5,
+            0,
+            650,
+            700,
+            0,
+            140,
+            650,
+            700,
+            0,
+            480,
+            50,
+            250,
+            0,
+            *([0] * 10),
+            0,
+            0,
+            0,
+            0,
+            b"UNIF",
+            0x0040,
+            min(chars),
+            min(max(chars), 0xFFFF),
+            ASCENT,
+            DESCENT,
+            0,
+            ASCENT,
+            -DESCENT,
+            0x00040000,
+            0,
+            0,
+            ASCENT,
+            0,
+            0x3000,
+            0,
+        ),
+        b"post": struct.pack(">IIhhIIIII", 0x00030000, 0, -2 * PIXEL, PIXEL, 1, 0, 0, 0, 0),
+    }
+
+    tags = sorted(tables)
+    count = len(tags)
+    search = 2 ** (count.bit_length() - 1)
+    header = struct.pack(
+        ">IHHHH", 0x00010000, count, search * 16, search.bit_length() - 1, count * 16 - search * 16
+    )
+    offset = len(header) + 16 * count
+    directory, body = b"", b""
+    for tag in tags:
+        data = tables[tag]
+        directory += struct.pack(">4sIII", tag, checksum(data), offset + len(body), len(data))
+        body += data + b"\0" * (-len(data) % 4)
+    font = bytearray(header + directory + body)
+
+    # head.checkSumAdjustment 在 head 表偏移 8 处
+    head = offset + sum(len(tables[t]) + (-len(tables[t]) % 4) for t in tags[: tags.index(b"head")])
+    adjustment = (0xB1B0AFBA - checksum(bytes(font))) & 0xFFFFFFFF
+    font[head + 8:head + 12] = struct.pack(">I", adjustment)
+
+    with open(out_path, "wb") as f:
+        f.write(font)
+    print(f"{len(chars)} glyphs, {len(font)} bytes")
+
+
+if __name__ == "__main__":
+    build(sys.argv[1], sys.argv[2])
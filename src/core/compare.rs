@@ -0,0 +1,281 @@
+use anyhow::{anyhow, Result};
+
+use crate::core::image::{Image, Rect};
+
+/// 比较参数
+#[derive(Debug, Clone, Default)]
+pub struct CompareOptions {
+    /// 单个通道允许的最大差值，超过才算不同
+    pub tolerance: u8,
+    /// 忽略的区域，例如时钟、光标
+    pub ignore: Vec<Rect>,
+}
+
+/// 逐像素比较的结果
+#[derive(Debug, Clone)]
+pub struct Diff {
+    width: u32,
+    height: u32,
+    mask: Vec<bool>,
+    different: usize,
+}
+
+impl Diff {
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// 每个像素是否不同，按行存储
+    pub fn mask(&self) -> &[bool] {
+        &self.mask
+    }
+
+    pub fn different_pixels(&self) -> usize {
+        self.different
+    }
+
+    /// 不同像素占全部像素的比例
+    pub fn ratio(&self) -> f64 {
+        if self.mask.is_empty() {
+            return 0.0;
+        }
+        self.different as f64 / self.mask.len() as f64
+    }
+
+    pub fn is_match(&self) -> bool {
+        self.different == 0
+    }
+
+    /// 以 base 的淡化灰度图为底，把不同的像素标成红色，base 必须与比较的图同尺寸
+    pub fn highlight(&self, base: &Image) -> Result<Image> {
+        if base.width() != self.width || base.height() != self.height {
+            return Err(anyhow!(
+                "Image size mismatch: {}x{} vs {}x{}",
+                base.width(),
+                base.height(),
+                self.width,
+                self.height
+            ));
+        }
+
+        let rgba = base
+            .rgba()
+            .chunks_exact(4)
+            .zip(&self.mask)
+            .flat_map(|(p, &different)| {
+                if different {
+                    [255, 0, 0, 255]
+                } else {
+                    let luma = luma(p) as u8;
+                    let v = 128 + luma / 2;
+                    [v, v, v, 255]
+                }
+            })
+            .collect();
+
+        Ok(Image::new(self.width, self.height, rgba))
+    }
+}
+
+fn check_size(a: &Image, b: &Image) -> Result<()> {
+    if a.width() != b.width() || a.height() != b.height() {
+        return Err(anyhow!(
+            "Image size mismatch: {}x{} vs {}x{}",
+            a.width(),
+            a.height(),
+            b.width(),
+            b.height()
+        ));
+    }
+    Ok(())
+}
+
+/// 把 a 中被忽略区域的像素拷贝到 b 的副本上，这样这些区域在所有指标里都视为相同
+fn without_ignored(a: &Image, b: &Image, ignore: &[Rect]) -> Image {
+    let mut b = b.clone();
+    for rect in ignore {
+        if let Some(view) = a.sub_view(*rect) {
+            let clip = rect.intersect(&a.bounds()).unwrap_or_default();
+            b.copy_from(&view, clip.x, clip.y);
+        }
+    }
+    b
+}
+
+fn luma(p: &[u8]) -> f64 {
+    0.299 * p[0] as f64 + 0.587 * p[1] as f64 + 0.114 * p[2] as f64
+}
+
+/// 逐像素比较两张同尺寸的图
+pub fn diff(a: &Image, b: &Image, options: &CompareOptions) -> Result<Diff> {
+    check_size(a, b)?;
+    let b = without_ignored(a, b, &options.ignore);
+
+    let mask: Vec<bool> = a
+        .rgba()
+        .chunks_exact(4)
+        .zip(b.rgba().chunks_exact(4))
+        .map(|(pa, pb)| {
+            pa.iter()
+                .zip(pb)
+                .any(|(ca, cb)| ca.abs_diff(*cb) > options.tolerance)
+        })
+        .collect();
+    let different = mask.iter().filter(|&&d| d).count();
+
+    Ok(Diff {
+        width: a.width(),
+        height: a.height(),
+        mask,
+        different,
+    })
+}
+
+/// 峰值信噪比（dB），只计算 RGB 通道，两张图完全相同时返回无穷大
+pub fn psnr(a: &Image, b: &Image, options: &CompareOptions) -> Result<f64> {
+    check_size(a, b)?;
+    let b = without_ignored(a, b, &options.ignore);
+
+    let mut sum = 0f64;
+    let mut count = 0usize;
+    for (pa, pb) in a.rgba().chunks_exact(4).zip(b.rgba().chunks_exact(4)) {
+        for c in 0..3 {
+            let d = pa[c] as f64 - pb[c] as f64;
+            sum += d * d;
+        }
+        count += 3;
+    }
+
+    if count == 0 || sum == 0.0 {
+        return Ok(f64::INFINITY);
+    }
+
+    let mse = sum / count as f64;
+    Ok(10.0 * (255.0 * 255.0 / mse).log10())
+}
+
+/// 窗口起点：从 0 开始每次前进 step，最后一个窗口贴齐末端，保证覆盖到边缘
+fn window_starts(len: u32, window: u32, step: u32) -> Vec<u32> {
+    let last = len - window;
+    let mut starts: Vec<u32> = (0..=last).step_by(step as usize).collect();
+    if starts.last() != Some(&last) {
+        starts.push(last);
+    }
+    starts
+}
+
+/// 结构相似度，在亮度通道上用 8x8 窗口（步长 4）计算后取平均，范围 [-1, 1]
+pub fn ssim(a: &Image, b: &Image, options: &CompareOptions) -> Result<f64> {
+    const WINDOW: u32 = 8;
+    const STEP: u32 = 4;
+    const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+    const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+    check_size(a, b)?;
+    let b = without_ignored(a, b, &options.ignore);
+
+    let la: Vec<f64> = a.rgba().chunks_exact(4).map(luma).collect();
+    let lb: Vec<f64> = b.rgba().chunks_exact(4).map(luma).collect();
+    let width = a.width();
+    let window_w = WINDOW.min(width);
+    let window_h = WINDOW.min(a.height());
+
+    if window_w == 0 || window_h == 0 {
+        return Ok(1.0);
+    }
+
+    let columns = window_starts(width, window_w, STEP);
+    let rows = window_starts(a.height(), window_h, STEP);
+    let mut total = 0f64;
+    let mut windows = 0usize;
+    for &y in &rows {
+        for &x in &columns {
+            let n = (window_w * window_h) as f64;
+            let (mut sa, mut sb, mut saa, mut sbb, mut sab) = (0f64, 0f64, 0f64, 0f64, 0f64);
+
+            for wy in y..y + window_h {
+                for wx in x..x + window_w {
+                    let i = (wy * width + wx) as usize;
+                    let (va, vb) = (la[i], lb[i]);
+                    sa += va;
+                    sb += vb;
+                    saa += va * va;
+                    sbb += vb * vb;
+                    sab += va * vb;
+                }
+            }
+
+            let (ma, mb) = (sa / n, sb / n);
+            let var_a = saa / n - ma * ma;
+            let var_b = sbb / n - mb * mb;
+            let cov = sab / n - ma * mb;
+
+            total += ((2.0 * ma * mb + C1) * (2.0 * cov + C2))
+                / ((ma * ma + mb * mb + C1) * (var_a + var_b + C2));
+            windows += 1;
+        }
+    }
+
+    Ok(total / windows as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u32, height: u32, v: u8) -> Image {
+        Image::new(
+            width,
+            height,
+            [v, v, v, 255].repeat((width * height) as usize),
+        )
+    }
+
+    #[test]
+    fn identical_images_match() {
+        let a = solid(16, 16, 100);
+        let options = CompareOptions::default();
+
+        assert!(diff(&a, &a, &options).unwrap().is_match());
+        assert_eq!(psnr(&a, &a, &options).unwrap(), f64::INFINITY);
+        assert!((ssim(&a, &a, &options).unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tolerance_and_ignored_regions() {
+        let a = solid(16, 16, 100);
+        let mut b = solid(16, 16, 102);
+        b.copy_from(&solid(4, 4, 0).view(), 10, 10);
+
+        let mut options = CompareOptions {
+            tolerance: 2,
+            ignore: Vec::new(),
+        };
+        let result = diff(&a, &b, &options).unwrap();
+        assert_eq!(result.different_pixels(), 16);
+        let highlight = result.highlight(&a).unwrap();
+        assert_eq!(highlight.view().pixel(10, 10), [255, 0, 0, 255]);
+        assert!(result.highlight(&solid(8, 8, 0)).is_err());
+
+        options.ignore.push(Rect::new(10, 10, 4, 4));
+        assert!(diff(&a, &b, &options).unwrap().is_match());
+        assert!(diff(&a, &solid(8, 8, 0), &options).is_err());
+    }
+
+    #[test]
+    fn ssim_covers_right_and_bottom_edges() {
+        // 10x10 时步长 4 的窗口只从 0 开始，最后两行两列需要额外的贴边窗口
+        let a = solid(10, 10, 100);
+        let mut b = a.clone();
+        b.copy_from(&solid(1, 1, 255).view(), 9, 9);
+
+        let options = CompareOptions::default();
+        assert!(ssim(&a, &b, &options).unwrap() < 0.99);
+        assert_eq!(window_starts(10, 8, 4), vec![0, 2]);
+        assert_eq!(window_starts(16, 8, 4), vec![0, 4, 8]);
+    }
+}
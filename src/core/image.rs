@@ -1,5 +1,153 @@
-use png::{BitDepth, ColorType, Encoder, EncodingError};
+use png::{BitDepth, ColorType, Decoder, DecodingError, Encoder, EncodingError, Transformations};
 
+mod beautify;
+mod blend;
+mod color;
+mod contrast;
+mod hash;
+mod loupe;
+mod palette;
+mod redact;
+mod resize;
+mod svg;
+mod transform;
+mod trim;
+mod watermark;
+
+pub use beautify::{Background, Beautify, Shadow};
+pub use blend::{blend_pixel, AlphaMode, BlendMode};
+pub use color::Color;
+pub use contrast::{Contrast, DetectedContrast};
+pub use hash::ImageHash;
+pub use loupe::{Loupe, Readout};
+pub use palette::Swatch;
+pub use redact::{Redaction, Region, MIN_PIXELATE_BLOCK};
+pub use resize::Filter;
+pub use svg::SvgRaster;
+pub use watermark::{Position, Stamp, Tile, Watermark};
+
+/// 图像上的矩形区域，坐标含义与 `capture_screen_area` 的参数一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rect {
+  pub x: i32,
+  pub y: i32,
+  pub width: u32,
+  pub height: u32,
+}
+
+impl Rect {
+  pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+    Rect {
+      x,
+      y,
+      width,
+      height,
+    }
+  }
+
+  /// 右边界，超出 i32 范围时取 i32::MAX
+  pub fn right(&self) -> i32 {
+    self.x.saturating_add_unsigned(self.width)
+  }
+
+  /// 下边界，超出 i32 范围时取 i32::MAX
+  pub fn bottom(&self) -> i32 {
+    self.y.saturating_add_unsigned(self.height)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.width == 0 || self.height == 0
+  }
+
+  pub fn contains(&self, x: i32, y: i32) -> bool {
+    x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+  }
+
+  /// 两个矩形的交集，不相交时返回 None
+  pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+    let left = self.x.max(other.x);
+    let top = self.y.max(other.y);
+    let right = self.right().min(other.right());
+    let bottom = self.bottom().min(other.bottom());
+
+    if right <= left || bottom <= top {
+      return None;
+    }
+
+    Some(Rect::new(
+      left,
+      top,
+      right.abs_diff(left),
+      bottom.abs_diff(top),
+    ))
+  }
+}
+
+/// 借用 Image 中一块区域的只读视图，不拷贝像素
+///
+/// `stride` 是相邻两行起始位置之间的字节数，子视图与原图共享同一个 stride
+#[derive(Debug, Clone, Copy)]
+pub struct ImageView<'a> {
+  width: u32,
+  height: u32,
+  stride: usize,
+  rgba: &'a [u8],
+}
+
+impl<'a> ImageView<'a> {
+  pub fn width(&self) -> u32 {
+    self.width
+  }
+
+  pub fn height(&self) -> u32 {
+    self.height
+  }
+
+  pub fn stride(&self) -> usize {
+    self.stride
+  }
+
+  /// 第 y 行的 RGBA 数据，长度为 width * 4
+  pub fn row(&self, y: u32) -> &'a [u8] {
+    let start = y as usize * self.stride;
+    &self.rgba[start..start + self.width as usize * 4]
+  }
+
+  pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+    let i = y as usize * self.stride + x as usize * 4;
+    [
+      self.rgba[i],
+      self.rgba[i + 1],
+      self.rgba[i + 2],
+      self.rgba[i + 3],
+    ]
+  }
+
+  /// 视图内的子区域，rect 相对于当前视图，超出部分会被裁掉
+  pub fn sub_view(&self, rect: Rect) -> Option<ImageView<'a>> {
+    let rect = rect.intersect(&Rect::new(0, 0, self.width, self.height))?;
+    let start = rect.y as usize * self.stride + rect.x as usize * 4;
+    let end = (rect.bottom() as usize - 1) * self.stride + rect.right() as usize * 4;
+
+    Some(ImageView {
+      width: rect.width,
+      height: rect.height,
+      stride: self.stride,
+      rgba: &self.rgba[start..end],
+    })
+  }
+
+  pub fn to_image(self) -> Image {
+    let mut rgba = Vec::with_capacity(self.width as usize * self.height as usize * 4);
+    for y in 0..self.height {
+      rgba.extend_from_slice(self.row(y));
+    }
+
+    Image::new(self.width, self.height, rgba)
+  }
+}
+
+#[derive(Debug, Clone)]
 pub struct Image {
   width: u32,
   height: u32,
@@ -56,6 +204,54 @@ impl Image {
     &self.rgba
   }
 
+  pub fn rgba_mut(&mut self) -> &mut [u8] {
+    &mut self.rgba
+  }
+
+  pub fn bounds(&self) -> Rect {
+    Rect::new(0, 0, self.width, self.height)
+  }
+
+  /// 整张图的视图
+  pub fn view(&self) -> ImageView<'_> {
+    ImageView {
+      width: self.width,
+      height: self.height,
+      stride: self.width as usize * 4,
+      rgba: &self.rgba,
+    }
+  }
+
+  /// 区域视图，rect 超出图像的部分会被裁掉，完全不相交时返回 None
+  pub fn sub_view(&self, rect: Rect) -> Option<ImageView<'_>> {
+    self.view().sub_view(rect)
+  }
+
+  /// 裁剪出一张新图，rect 超出图像的部分会被裁掉，完全不相交时返回 None
+  pub fn crop(&self, rect: Rect) -> Option<Image> {
+    self.sub_view(rect).map(ImageView::to_image)
+  }
+
+  /// 把 view 的像素拷贝到 (x, y) 处，超出当前图像的部分会被忽略
+  pub fn copy_from(&mut self, view: &ImageView, x: i32, y: i32) {
+    let target = Rect::new(x, y, view.width(), view.height());
+    let clip = match target.intersect(&self.bounds()) {
+      Some(clip) => clip,
+      None => return,
+    };
+
+    let src_x = (clip.x - x) as usize * 4;
+    let src_y = (clip.y - y) as u32;
+    let len = clip.width as usize * 4;
+    let stride = self.width as usize * 4;
+
+    for row in 0..clip.height {
+      let src = &view.row(src_y + row)[src_x..src_x + len];
+      let start = (clip.y as usize + row as usize) * stride + clip.x as usize * 4;
+      self.rgba[start..start + len].copy_from_slice(src);
+    }
+  }
+
   pub fn to_png(&self) -> Result<Vec<u8>, EncodingError> {
     let mut buffer = Vec::new();
     let mut encoder = Encoder::new(&mut buffer, self.width, self.height);
@@ -69,6 +265,31 @@ impl Image {
 
     Ok(buffer)
   }
+
+  /// 解码 PNG，灰度、调色板、16 位等格式统一转为 8 位 RGBA
+  pub fn from_png(data: &[u8]) -> Result<Image, DecodingError> {
+    let mut decoder = Decoder::new(data);
+    decoder.set_transformations(Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+
+    let rgba = match info.color_type {
+      ColorType::Rgba => buffer,
+      ColorType::Rgb => buffer
+        .chunks_exact(3)
+        .flat_map(|p| [p[0], p[1], p[2], 255])
+        .collect(),
+      ColorType::GrayscaleAlpha => buffer
+        .chunks_exact(2)
+        .flat_map(|p| [p[0], p[0], p[0], p[1]])
+        .collect(),
+      _ => buffer.iter().flat_map(|&v| [v, v, v, 255]).collect(),
+    };
+
+    Ok(Image::new(info.width, info.height, rgba))
+  }
 }
 
 impl Into<Vec<u8>> for Image {
@@ -76,3 +297,58 @@ impl Into<Vec<u8>> for Image {
     self.rgba
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn png_round_trip() {
+    let image = Image::new(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 128]);
+    let decoded = Image::from_png(&image.to_png().unwrap()).unwrap();
+
+    assert_eq!((decoded.width(), decoded.height()), (2, 1));
+    assert_eq!(decoded.rgba(), image.rgba());
+  }
+
+  fn gradient(width: u32, height: u32) -> Image {
+    let mut rgba = Vec::new();
+    for y in 0..height {
+      for x in 0..width {
+        rgba.extend_from_slice(&[x as u8, y as u8, 0, 255]);
+      }
+    }
+    Image::new(width, height, rgba)
+  }
+
+  #[test]
+  fn crop_clips_to_bounds() {
+    let image = gradient(8, 6);
+    let cropped = image.crop(Rect::new(5, 4, 10, 10)).unwrap();
+
+    assert_eq!((cropped.width(), cropped.height()), (3, 2));
+    assert_eq!(&cropped.rgba()[0..4], &[5, 4, 0, 255]);
+    assert!(image.crop(Rect::new(20, 20, 4, 4)).is_none());
+
+    // 超大尺寸不会溢出
+    let huge = Rect::new(1, 1, u32::MAX, u32::MAX);
+    assert_eq!(huge.right(), i32::MAX);
+    assert_eq!(huge.intersect(&image.bounds()), Some(Rect::new(1, 1, 7, 5)));
+    let wide = Rect::new(-5, 0, u32::MAX, 1).intersect(&Rect::new(-3, 0, u32::MAX, 1));
+    assert_eq!(wide, Some(Rect::new(-3, 0, i32::MAX as u32 + 3, 1)));
+  }
+
+  #[test]
+  fn copy_from_sub_view() {
+    let source = gradient(8, 8);
+    let view = source.sub_view(Rect::new(2, 3, 4, 4)).unwrap();
+    assert_eq!(view.stride(), 32);
+    assert_eq!(view.pixel(1, 1), [3, 4, 0, 255]);
+
+    let mut target = Image::new(4, 4, vec![0; 64]);
+    target.copy_from(&view, -1, 2);
+
+    assert_eq!(target.view().pixel(0, 2), [3, 3, 0, 255]);
+    assert_eq!(target.view().pixel(0, 1), [0, 0, 0, 0]);
+  }
+}
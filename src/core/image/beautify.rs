@@ -0,0 +1,232 @@
+use super::blend::{blend_pixel, AlphaMode, BlendMode};
+use super::redact::blur;
+use super::Image;
+
+/// 画布背景
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Background {
+  Solid([u8; 4]),
+  /// 线性渐变，angle 为角度，0 表示从左到右，90 表示从上到下
+  LinearGradient {
+    from: [u8; 4],
+    to: [u8; 4],
+    angle: f32,
+  },
+}
+
+/// 投影
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Shadow {
+  pub offset_x: i32,
+  pub offset_y: i32,
+  /// 模糊半径
+  pub blur: u32,
+  pub color: [u8; 4],
+}
+
+impl Default for Shadow {
+  fn default() -> Self {
+    Shadow {
+      offset_x: 0,
+      offset_y: 8,
+      blur: 24,
+      color: [0, 0, 0, 96],
+    }
+  }
+}
+
+/// 截图美化参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Beautify {
+  /// 截图四周留白
+  pub padding: u32,
+  pub background: Background,
+  /// 截图圆角半径
+  pub corner_radius: f32,
+  pub shadow: Option<Shadow>,
+}
+
+impl Default for Beautify {
+  fn default() -> Self {
+    Beautify {
+      padding: 64,
+      background: Background::LinearGradient {
+        from: [99, 102, 241, 255],
+        to: [236, 72, 153, 255],
+        angle: 45.0,
+      },
+      corner_radius: 12.0,
+      shadow: Some(Shadow::default()),
+    }
+  }
+}
+
+/// 圆角矩形在像素 (x, y) 处的覆盖率，用于抗锯齿
+fn rounded_coverage(x: u32, y: u32, width: u32, height: u32, radius: f32) -> f32 {
+  let radius = radius.min(width as f32 / 2.0).min(height as f32 / 2.0);
+  if radius <= 0.0 {
+    return 1.0;
+  }
+
+  // 以像素中心到最近的圆角圆心的距离计算
+  let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+  let cx = px.clamp(radius, width as f32 - radius);
+  let cy = py.clamp(radius, height as f32 - radius);
+  let distance = ((px - cx).powi(2) + (py - cy).powi(2)).sqrt();
+
+  (radius - distance + 0.5).clamp(0.0, 1.0)
+}
+
+impl Background {
+  fn fill(&self, width: u32, height: u32) -> Image {
+    match *self {
+      Background::Solid(color) => Image::new(
+        width,
+        height,
+        color.repeat(width as usize * height as usize),
+      ),
+      Background::LinearGradient { from, to, angle } => {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        // 四个角在渐变方向上的投影范围
+        let corners = [
+          (0.0, 0.0),
+          (width as f32, 0.0),
+          (0.0, height as f32),
+          (width as f32, height as f32),
+        ];
+        let projections = corners.map(|(x, y)| x * cos + y * sin);
+        let min = projections.iter().cloned().fold(f32::MAX, f32::min);
+        let max = projections.iter().cloned().fold(f32::MIN, f32::max);
+        let range = (max - min).max(f32::EPSILON);
+
+        let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height {
+          for x in 0..width {
+            let t = ((x as f32 + 0.5) * cos + (y as f32 + 0.5) * sin - min) / range;
+            for c in 0..4 {
+              rgba.push((from[c] as f32 + (to[c] as f32 - from[c] as f32) * t).round() as u8);
+            }
+          }
+        }
+        Image::new(width, height, rgba)
+      }
+    }
+  }
+}
+
+impl Image {
+  /// 美化截图：加留白、背景、圆角和投影，返回新图
+  pub fn beautify(&self, options: &Beautify) -> Image {
+    let padding = options.padding;
+    let width = self.width + padding * 2;
+    let height = self.height + padding * 2;
+    let mut canvas = options.background.fill(width, height);
+
+    if let Some(shadow) = options.shadow {
+      // 用截图的圆角形状生成投影层，模糊后叠加到背景上
+      // 透明像素也要带上投影颜色，否则模糊时边缘会发黑
+      let mut color = shadow.color;
+      color[3] = 0;
+      let mut layer = Image::new(
+        width,
+        height,
+        color.repeat(width as usize * height as usize),
+      );
+      for y in 0..self.height {
+        for x in 0..self.width {
+          let tx = x as i32 + padding as i32 + shadow.offset_x;
+          let ty = y as i32 + padding as i32 + shadow.offset_y;
+          if tx < 0 || ty < 0 || tx >= width as i32 || ty >= height as i32 {
+            continue;
+          }
+
+          let coverage = rounded_coverage(x, y, self.width, self.height, options.corner_radius);
+          let i = (ty as usize * width as usize + tx as usize) * 4;
+          layer.rgba[i + 3] = (shadow.color[3] as f32 * coverage).round() as u8;
+        }
+      }
+
+      let layer = blur(&layer, shadow.blur);
+      for (dst, src) in canvas
+        .rgba
+        .chunks_exact_mut(4)
+        .zip(layer.rgba.chunks_exact(4))
+      {
+        blend_pixel(dst, src, BlendMode::Over, 1.0, AlphaMode::Straight);
+      }
+    }
+
+    for y in 0..self.height {
+      for x in 0..self.width {
+        let coverage = rounded_coverage(x, y, self.width, self.height, options.corner_radius);
+        let src = (y as usize * self.width as usize + x as usize) * 4;
+        let dst = ((y + padding) as usize * width as usize + (x + padding) as usize) * 4;
+        blend_pixel(
+          &mut canvas.rgba[dst..dst + 4],
+          &self.rgba[src..src + 4],
+          BlendMode::Over,
+          coverage,
+          AlphaMode::Straight,
+        );
+      }
+    }
+
+    canvas
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn beautify_adds_padding_and_rounds_corners() {
+    let image = Image::new(20, 10, [255, 255, 255, 255].repeat(200));
+    let options = Beautify {
+      padding: 5,
+      background: Background::Solid([0, 0, 0, 255]),
+      corner_radius: 4.0,
+      shadow: None,
+    };
+    let result = image.beautify(&options);
+
+    assert_eq!((result.width(), result.height()), (30, 20));
+    // 圆角外是背景，中心是截图
+    assert_eq!(result.view().pixel(5, 5), [0, 0, 0, 255]);
+    assert_eq!(result.view().pixel(15, 10), [255, 255, 255, 255]);
+    assert_eq!(result.view().pixel(0, 0), [0, 0, 0, 255]);
+  }
+
+  #[test]
+  fn shadow_darkens_background() {
+    let image = Image::new(10, 10, [255, 255, 255, 255].repeat(100));
+    let options = Beautify {
+      padding: 10,
+      background: Background::Solid([200, 200, 200, 255]),
+      corner_radius: 0.0,
+      shadow: Some(Shadow {
+        offset_x: 0,
+        offset_y: 4,
+        blur: 2,
+        color: [0, 0, 0, 255],
+      }),
+    };
+    let result = image.beautify(&options);
+
+    assert!(result.view().pixel(15, 22)[0] < 200);
+    assert_eq!(result.view().pixel(15, 2), [200, 200, 200, 255]);
+  }
+
+  #[test]
+  fn gradient_goes_from_start_to_end() {
+    let background = Background::LinearGradient {
+      from: [0, 0, 0, 255],
+      to: [255, 255, 255, 255],
+      angle: 0.0,
+    };
+    let image = background.fill(100, 1);
+
+    assert!(image.view().pixel(0, 0)[0] < 5);
+    assert!(image.view().pixel(99, 0)[0] > 250);
+  }
+}
@@ -0,0 +1,221 @@
+use super::{Image, ImageView, Rect};
+
+/// 混合模式
+///
+/// Over/In/Out 为 Porter-Duff 合成，Multiply/Screen/Darken 按 W3C Compositing
+/// 规范的可分离混合模式计算，合成方式为 source-over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+  #[default]
+  Over,
+  In,
+  Out,
+  Multiply,
+  Screen,
+  Darken,
+}
+
+/// 像素数据的 alpha 存储方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlphaMode {
+  /// 颜色未乘 alpha，截图和 PNG 都是这种
+  #[default]
+  Straight,
+  /// 颜色已乘 alpha
+  Premultiplied,
+}
+
+fn load(p: &[u8], alpha: AlphaMode) -> [f32; 4] {
+  let a = p[3] as f32 / 255.0;
+  let scale = match alpha {
+    AlphaMode::Straight => a / 255.0,
+    AlphaMode::Premultiplied => 1.0 / 255.0,
+  };
+  [
+    p[0] as f32 * scale,
+    p[1] as f32 * scale,
+    p[2] as f32 * scale,
+    a,
+  ]
+}
+
+fn store(p: &mut [u8], v: [f32; 4], alpha: AlphaMode) {
+  let a = v[3].clamp(0.0, 1.0);
+  let scale = match alpha {
+    AlphaMode::Straight if a > 0.0 => 255.0 / a,
+    AlphaMode::Straight => 0.0,
+    AlphaMode::Premultiplied => 255.0,
+  };
+  for c in 0..3 {
+    p[c] = (v[c] * scale).round().clamp(0.0, 255.0) as u8;
+  }
+  p[3] = (a * 255.0).round() as u8;
+}
+
+/// 把 src 像素按 mode 混合到 dst 像素上，opacity 会先乘到 src 上
+pub fn blend_pixel(dst: &mut [u8], src: &[u8], mode: BlendMode, opacity: f32, alpha: AlphaMode) {
+  let mut s = load(src, alpha);
+  let d = load(dst, alpha);
+  s.iter_mut().for_each(|v| *v *= opacity);
+
+  let (sa, da) = (s[3], d[3]);
+  let mut out = [0f32; 4];
+  match mode {
+    BlendMode::Over => {
+      for c in 0..4 {
+        out[c] = s[c] + d[c] * (1.0 - sa);
+      }
+    }
+    BlendMode::In => {
+      for c in 0..4 {
+        out[c] = s[c] * da;
+      }
+    }
+    BlendMode::Out => {
+      for c in 0..4 {
+        out[c] = s[c] * (1.0 - da);
+      }
+    }
+    BlendMode::Multiply | BlendMode::Screen | BlendMode::Darken => {
+      for c in 0..3 {
+        // 混合函数作用在未预乘的颜色上
+        let cs = if sa > 0.0 { s[c] / sa } else { 0.0 };
+        let cd = if da > 0.0 { d[c] / da } else { 0.0 };
+        let b = match mode {
+          BlendMode::Multiply => cs * cd,
+          BlendMode::Screen => cs + cd - cs * cd,
+          _ => cs.min(cd),
+        };
+        out[c] = s[c] * (1.0 - da) + d[c] * (1.0 - sa) + sa * da * b;
+      }
+      out[3] = sa + da * (1.0 - sa);
+    }
+  }
+
+  store(dst, out, alpha);
+}
+
+impl Image {
+  /// 把 src 以 mode 混合到 (x, y) 处，straight alpha
+  ///
+  /// 只处理两者重叠的区域，In/Out 不会清除 src 以外的像素
+  pub fn blend(&mut self, src: &ImageView, x: i32, y: i32, mode: BlendMode, opacity: f32) {
+    self.blend_with_alpha(src, x, y, mode, opacity, AlphaMode::Straight);
+  }
+
+  /// 同 `blend`，可以指定两张图的 alpha 存储方式
+  pub fn blend_with_alpha(
+    &mut self,
+    src: &ImageView,
+    x: i32,
+    y: i32,
+    mode: BlendMode,
+    opacity: f32,
+    alpha: AlphaMode,
+  ) {
+    let target = Rect::new(x, y, src.width(), src.height());
+    let clip = match target.intersect(&self.bounds()) {
+      Some(clip) => clip,
+      None => return,
+    };
+
+    let opacity = opacity.clamp(0.0, 1.0);
+    let stride = self.width as usize * 4;
+    for row in 0..clip.height {
+      let src_row = src.row((clip.y - y) as u32 + row);
+      let start = (clip.y as usize + row as usize) * stride + clip.x as usize * 4;
+      let dst_row = &mut self.rgba[start..start + clip.width as usize * 4];
+      let src_row = &src_row[(clip.x - x) as usize * 4..];
+
+      for (d, s) in dst_row.chunks_exact_mut(4).zip(src_row.chunks_exact(4)) {
+        blend_pixel(d, s, mode, opacity, alpha);
+      }
+    }
+  }
+
+  /// 颜色乘以 alpha
+  pub fn premultiply(&mut self) {
+    for p in self.rgba.chunks_exact_mut(4) {
+      let a = p[3] as u32;
+      for c in &mut p[0..3] {
+        *c = ((*c as u32 * a + 127) / 255) as u8;
+      }
+    }
+  }
+
+  /// 颜色除以 alpha，alpha 为 0 的像素颜色置 0
+  pub fn unpremultiply(&mut self) {
+    for p in self.rgba.chunks_exact_mut(4) {
+      let a = p[3] as u32;
+      for c in &mut p[0..3] {
+        *c = match a {
+          0 => 0,
+          _ => ((*c as u32 * 255 + a / 2) / a).min(255) as u8,
+        };
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn solid(color: [u8; 4]) -> Image {
+    Image::new(2, 2, color.repeat(4))
+  }
+
+  fn blend(dst: [u8; 4], src: [u8; 4], mode: BlendMode) -> [u8; 4] {
+    let mut image = solid(dst);
+    image.blend(&solid(src).view(), 0, 0, mode, 1.0);
+    image.view().pixel(0, 0)
+  }
+
+  #[test]
+  fn porter_duff() {
+    assert_eq!(
+      blend([0, 0, 255, 255], [255, 0, 0, 128], BlendMode::Over),
+      [128, 0, 127, 255]
+    );
+    assert_eq!(
+      blend([0, 0, 255, 128], [255, 0, 0, 255], BlendMode::In),
+      [255, 0, 0, 128]
+    );
+    assert_eq!(
+      blend([0, 0, 255, 255], [255, 0, 0, 255], BlendMode::Out),
+      [0, 0, 0, 0]
+    );
+  }
+
+  #[test]
+  fn separable_modes() {
+    let (dst, src) = ([200, 100, 50, 255], [128, 128, 128, 255]);
+    assert_eq!(blend(dst, src, BlendMode::Multiply), [100, 50, 25, 255]);
+    assert_eq!(blend(dst, src, BlendMode::Screen), [228, 178, 153, 255]);
+    assert_eq!(blend(dst, src, BlendMode::Darken), [128, 100, 50, 255]);
+  }
+
+  #[test]
+  fn premultiplied_matches_straight() {
+    let mut straight = solid([0, 0, 255, 255]);
+    straight.blend(&solid([255, 0, 0, 128]).view(), 1, 1, BlendMode::Over, 0.5);
+
+    let mut premultiplied = solid([0, 0, 255, 255]);
+    let mut src = solid([255, 0, 0, 128]);
+    src.premultiply();
+    premultiplied.blend_with_alpha(
+      &src.view(),
+      1,
+      1,
+      BlendMode::Over,
+      0.5,
+      AlphaMode::Premultiplied,
+    );
+
+    assert_eq!(
+      straight.view().pixel(1, 1),
+      premultiplied.view().pixel(1, 1)
+    );
+    assert_eq!(straight.view().pixel(0, 0), [0, 0, 255, 255]);
+  }
+}
@@ -0,0 +1,263 @@
+use std::fmt;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{Image, Rect};
+
+/// RGBA 颜色，straight alpha
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Color {
+  pub r: u8,
+  pub g: u8,
+  pub b: u8,
+  pub a: u8,
+}
+
+impl Color {
+  pub fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+    Color { r, g, b, a }
+  }
+
+  pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+    Color::new(r, g, b, 255)
+  }
+
+  /// 解析 `#RGB`、`#RRGGBB`、`#RRGGBBAA`，`#` 可省略
+  pub fn from_hex(hex: &str) -> Option<Color> {
+    let hex = hex.trim().trim_start_matches('#');
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+
+    match hex.len() {
+      3 => {
+        let short = |i: usize| {
+          u8::from_str_radix(hex.get(i..i + 1)?, 16)
+            .ok()
+            .map(|v| v * 17)
+        };
+        Some(Color::rgb(short(0)?, short(1)?, short(2)?))
+      }
+      6 => Some(Color::rgb(channel(0)?, channel(2)?, channel(4)?)),
+      8 => Some(Color::new(
+        channel(0)?,
+        channel(2)?,
+        channel(4)?,
+        channel(6)?,
+      )),
+      _ => None,
+    }
+  }
+
+  pub fn to_array(self) -> [u8; 4] {
+    [self.r, self.g, self.b, self.a]
+  }
+
+  /// `#RRGGBB`，不透明度不为 255 时为 `#RRGGBBAA`
+  pub fn to_hex(self) -> String {
+    if self.a == 255 {
+      format!("#{:02X}{:02X}{:02X}", self.r, self.g, self.b)
+    } else {
+      format!("#{:02X}{:02X}{:02X}{:02X}", self.r, self.g, self.b, self.a)
+    }
+  }
+
+  /// `rgb(r, g, b)`，不透明度不为 255 时为 `rgba(r, g, b, a)`
+  pub fn to_rgb_string(self) -> String {
+    if self.a == 255 {
+      format!("rgb({}, {}, {})", self.r, self.g, self.b)
+    } else {
+      format!(
+        "rgba({}, {}, {}, {:.2})",
+        self.r,
+        self.g,
+        self.b,
+        self.a as f32 / 255.0
+      )
+    }
+  }
+
+  fn normalized(self) -> (f32, f32, f32) {
+    (
+      self.r as f32 / 255.0,
+      self.g as f32 / 255.0,
+      self.b as f32 / 255.0,
+    )
+  }
+
+  /// 色相 [0, 360)，最大、最小分量与它们的差
+  fn hue(self) -> (f32, f32, f32, f32) {
+    let (r, g, b) = self.normalized();
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta == 0.0 {
+      0.0
+    } else if max == r {
+      60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+      60.0 * ((b - r) / delta + 2.0)
+    } else {
+      60.0 * ((r - g) / delta + 4.0)
+    };
+
+    (hue, max, min, delta)
+  }
+
+  /// (色相 0~360, 饱和度 0~100, 亮度 0~100)
+  pub fn to_hsl(self) -> (f32, f32, f32) {
+    let (hue, max, min, delta) = self.hue();
+    let lightness = (max + min) / 2.0;
+    let saturation = if delta == 0.0 {
+      0.0
+    } else {
+      delta / (1.0 - (2.0 * lightness - 1.0).abs())
+    };
+
+    (hue, saturation * 100.0, lightness * 100.0)
+  }
+
+  /// (色相 0~360, 饱和度 0~100, 明度 0~100)
+  pub fn to_hsv(self) -> (f32, f32, f32) {
+    let (hue, max, _, delta) = self.hue();
+    let saturation = if max == 0.0 { 0.0 } else { delta / max };
+
+    (hue, saturation * 100.0, max * 100.0)
+  }
+
+  /// (C, M, Y, K)，均为 0~100
+  pub fn to_cmyk(self) -> (f32, f32, f32, f32) {
+    let (r, g, b) = self.normalized();
+    let k = 1.0 - r.max(g).max(b);
+    if k >= 1.0 {
+      return (0.0, 0.0, 0.0, 100.0);
+    }
+
+    let c = (1.0 - r - k) / (1.0 - k);
+    let m = (1.0 - g - k) / (1.0 - k);
+    let y = (1.0 - b - k) / (1.0 - k);
+    (c * 100.0, m * 100.0, y * 100.0, k * 100.0)
+  }
+
+  /// `hsl(210, 50%, 40%)`
+  pub fn to_hsl_string(self) -> String {
+    let (h, s, l) = self.to_hsl();
+    format!("hsl({:.0}, {:.0}%, {:.0}%)", h, s, l)
+  }
+
+  /// `hsv(210, 67%, 60%)`
+  pub fn to_hsv_string(self) -> String {
+    let (h, s, v) = self.to_hsv();
+    format!("hsv({:.0}, {:.0}%, {:.0}%)", h, s, v)
+  }
+
+  /// `cmyk(67%, 33%, 0%, 40%)`
+  pub fn to_cmyk_string(self) -> String {
+    let (c, m, y, k) = self.to_cmyk();
+    format!("cmyk({:.0}%, {:.0}%, {:.0}%, {:.0}%)", c, m, y, k)
+  }
+}
+
+impl From<[u8; 4]> for Color {
+  fn from(rgba: [u8; 4]) -> Self {
+    Color::new(rgba[0], rgba[1], rgba[2], rgba[3])
+  }
+}
+
+impl From<Color> for [u8; 4] {
+  fn from(color: Color) -> Self {
+    color.to_array()
+  }
+}
+
+/// 序列化为 `#RRGGBB` / `#RRGGBBAA`，方便手工编辑
+impl Serialize for Color {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&self.to_hex())
+  }
+}
+
+impl<'de> Deserialize<'de> for Color {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    Color::from_hex(&hex).ok_or_else(|| D::Error::custom(format!("Invalid color {hex}")))
+  }
+}
+
+impl fmt::Display for Color {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.to_hex())
+  }
+}
+
+impl Image {
+  /// (x, y) 处的颜色，越界时返回 None
+  pub fn pixel(&self, x: u32, y: u32) -> Option<Color> {
+    if x >= self.width || y >= self.height {
+      return None;
+    }
+    Some(self.view().pixel(x, y).into())
+  }
+
+  /// 区域内的平均色，颜色按 alpha 加权，超出图像的部分会被忽略
+  pub fn average_color(&self, rect: Rect) -> Option<Color> {
+    let view = self.sub_view(rect)?;
+
+    let mut sum = [0u64; 4];
+    for y in 0..view.height() {
+      for p in view.row(y).chunks_exact(4) {
+        let a = p[3] as u64;
+        sum[0] += p[0] as u64 * a;
+        sum[1] += p[1] as u64 * a;
+        sum[2] += p[2] as u64 * a;
+        sum[3] += a;
+      }
+    }
+
+    let count = view.width() as u64 * view.height() as u64;
+    if sum[3] == 0 {
+      return Some(Color::new(0, 0, 0, 0));
+    }
+
+    let channel = |v: u64| ((v + sum[3] / 2) / sum[3]) as u8;
+    Some(Color::new(
+      channel(sum[0]),
+      channel(sum[1]),
+      channel(sum[2]),
+      ((sum[3] + count / 2) / count) as u8,
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn formats() {
+    let color = Color::rgb(51, 102, 153);
+
+    assert_eq!(color.to_hex(), "#336699");
+    assert_eq!(color.to_rgb_string(), "rgb(51, 102, 153)");
+    assert_eq!(color.to_hsl_string(), "hsl(210, 50%, 40%)");
+    assert_eq!(color.to_hsv_string(), "hsv(210, 67%, 60%)");
+    assert_eq!(color.to_cmyk_string(), "cmyk(67%, 33%, 0%, 40%)");
+    assert_eq!(Color::from_hex("#336699"), Some(color));
+    assert_eq!(Color::from_hex("fff"), Some(Color::rgb(255, 255, 255)));
+    assert_eq!(Color::new(0, 0, 0, 128).to_hex(), "#00000080");
+  }
+
+  #[test]
+  fn average_over_area() {
+    let mut rgba = [255, 0, 0, 255].repeat(2);
+    rgba.extend([0, 0, 255, 255].repeat(2));
+    let image = Image::new(2, 2, rgba);
+
+    assert_eq!(image.pixel(0, 1), Some(Color::rgb(0, 0, 255)));
+    assert_eq!(image.pixel(2, 0), None);
+    assert_eq!(
+      image.average_color(Rect::new(0, 0, 10, 10)),
+      Some(Color::rgb(128, 0, 128))
+    );
+  }
+}
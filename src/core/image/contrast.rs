@@ -0,0 +1,139 @@
+use super::{Color, Image, Rect};
+
+/// WCAG 2.x 对比度检查结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contrast {
+  /// 对比度，1 ~ 21
+  pub ratio: f32,
+  /// AA 级普通文字，>= 4.5
+  pub aa_normal: bool,
+  /// AA 级大号文字，>= 3
+  pub aa_large: bool,
+  /// AAA 级普通文字，>= 7
+  pub aaa_normal: bool,
+  /// AAA 级大号文字，>= 4.5
+  pub aaa_large: bool,
+}
+
+impl Contrast {
+  /// 前景色与背景色的对比度，半透明的前景先叠加到背景上，半透明的背景先叠加到白色上
+  pub fn new(foreground: Color, background: Color) -> Self {
+    let background = over(background, Color::rgb(255, 255, 255));
+    let foreground = over(foreground, background);
+    let ratio = foreground.contrast_ratio(&background);
+
+    // 按规范比较前不做四舍五入，4.499 不算通过
+    Contrast {
+      ratio,
+      aa_normal: ratio >= 4.5,
+      aa_large: ratio >= 3.0,
+      aaa_normal: ratio >= 7.0,
+      aaa_large: ratio >= 4.5,
+    }
+  }
+}
+
+/// 自动检测出的前景色、背景色及其对比度
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DetectedContrast {
+  pub foreground: Color,
+  pub background: Color,
+  pub contrast: Contrast,
+}
+
+fn over(top: Color, bottom: Color) -> Color {
+  let a = top.a as f32 / 255.0;
+  let mix = |t: u8, b: u8| (t as f32 * a + b as f32 * (1.0 - a)).round() as u8;
+  Color::rgb(
+    mix(top.r, bottom.r),
+    mix(top.g, bottom.g),
+    mix(top.b, bottom.b),
+  )
+}
+
+impl Color {
+  /// WCAG 定义的相对亮度，0 ~ 1，忽略 alpha
+  pub fn relative_luminance(&self) -> f32 {
+    let linear = |v: u8| {
+      let v = v as f32 / 255.0;
+      if v <= 0.04045 {
+        v / 12.92
+      } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+      }
+    };
+
+    0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+  }
+
+  /// 与 other 的对比度，与顺序无关，忽略 alpha
+  pub fn contrast_ratio(&self, other: &Color) -> f32 {
+    let a = self.relative_luminance();
+    let b = other.relative_luminance();
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+  }
+}
+
+impl Image {
+  /// 两个区域平均色之间的对比度，foreground 区域作为前景
+  pub fn contrast(&self, foreground: Rect, background: Rect) -> Option<Contrast> {
+    let foreground = self.average_color(foreground)?;
+    let background = self.average_color(background)?;
+    Some(Contrast::new(foreground, background))
+  }
+
+  /// 自动估计区域内的前景色和背景色
+  ///
+  /// 占比最大的颜色作为背景，其余占比不低于 1% 的颜色中与背景对比度最高的作为前景
+  pub fn detect_contrast(&self, rect: Rect) -> Option<DetectedContrast> {
+    let palette = self.crop(rect)?.palette(6);
+    let background = palette.first()?.color;
+    let foreground = palette[1..]
+      .iter()
+      .filter(|s| s.coverage >= 1.0)
+      .map(|s| s.color)
+      .max_by(|a, b| {
+        a.contrast_ratio(&background)
+          .total_cmp(&b.contrast_ratio(&background))
+      })?;
+
+    Some(DetectedContrast {
+      foreground,
+      background,
+      contrast: Contrast::new(foreground, background),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn wcag_ratios() {
+    let black = Color::rgb(0, 0, 0);
+    let white = Color::rgb(255, 255, 255);
+    assert!((black.contrast_ratio(&white) - 21.0).abs() < 0.01);
+
+    // #767676 在白底上刚好通过 AA
+    let gray = Contrast::new(Color::rgb(0x76, 0x76, 0x76), white);
+    assert!(gray.aa_normal && !gray.aaa_normal);
+    let light = Contrast::new(Color::rgb(0x77, 0x77, 0x77), white);
+    assert!(!light.aa_normal && light.aa_large);
+
+    // 半透明黑色叠加在白底上
+    let half = Contrast::new(Color::new(0, 0, 0, 128), white);
+    assert!(half.ratio > 3.0 && half.ratio < 5.0);
+  }
+
+  #[test]
+  fn detect_text_on_background() {
+    let mut image = Image::new(20, 10, [255, 255, 255, 255].repeat(200));
+    image.fill_rect(Rect::new(2, 3, 12, 3), Color::rgb(30, 30, 30));
+    let detected = image.detect_contrast(image.bounds()).unwrap();
+
+    assert_eq!(detected.background, Color::rgb(255, 255, 255));
+    assert_eq!(detected.foreground, Color::rgb(30, 30, 30));
+    assert!(detected.contrast.aaa_normal);
+  }
+}
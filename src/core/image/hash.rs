@@ -0,0 +1,180 @@
+use super::{Filter, Image};
+
+/// 64 位感知哈希，相似的图片哈希之间的汉明距离也小
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageHash(pub u64);
+
+impl ImageHash {
+  /// 汉明距离，即不同的位数
+  pub fn distance(&self, other: &ImageHash) -> u32 {
+    (self.0 ^ other.0).count_ones()
+  }
+
+  /// 距离不超过 threshold 即认为相似，64 位哈希一般取 5 ~ 10
+  pub fn is_similar(&self, other: &ImageHash, threshold: u32) -> bool {
+    self.distance(other) <= threshold
+  }
+
+  /// 按相似度把哈希分组，返回每组在 hashes 中的下标
+  ///
+  /// 相似关系是传递的：a 与 b 相似、b 与 c 相似时，三者分在同一组
+  pub fn group(hashes: &[ImageHash], threshold: u32) -> Vec<Vec<usize>> {
+    // 并查集
+    let mut parent: Vec<usize> = (0..hashes.len()).collect();
+    fn find(parent: &mut [usize], i: usize) -> usize {
+      let mut root = i;
+      while parent[root] != root {
+        root = parent[root];
+      }
+      parent[i] = root;
+      root
+    }
+
+    for i in 0..hashes.len() {
+      for j in i + 1..hashes.len() {
+        if hashes[i].is_similar(&hashes[j], threshold) {
+          let (a, b) = (find(&mut parent, i), find(&mut parent, j));
+          parent[b] = a;
+        }
+      }
+    }
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut roots: Vec<usize> = Vec::new();
+    for i in 0..hashes.len() {
+      let root = find(&mut parent, i);
+      match roots.iter().position(|&r| r == root) {
+        Some(g) => groups[g].push(i),
+        None => {
+          roots.push(root);
+          groups.push(vec![i]);
+        }
+      }
+    }
+
+    groups
+  }
+}
+
+impl Image {
+  /// 缩小到 width * height 后的亮度
+  fn luma(&self, width: u32, height: u32) -> Vec<f32> {
+    self
+      .resize(width, height, Filter::Bilinear)
+      .rgba
+      .chunks_exact(4)
+      .map(|p| 0.299 * p[0] as f32 + 0.587 * p[1] as f32 + 0.114 * p[2] as f32)
+      .collect()
+  }
+
+  /// 均值哈希：8x8 亮度与平均值比较
+  pub fn ahash(&self) -> ImageHash {
+    let luma = self.luma(8, 8);
+    let mean = luma.iter().sum::<f32>() / luma.len() as f32;
+    bits(luma.iter().map(|&v| v > mean))
+  }
+
+  /// 差值哈希：9x8 亮度中每个像素与右侧像素比较
+  pub fn dhash(&self) -> ImageHash {
+    let luma = self.luma(9, 8);
+    bits((0..8).flat_map(|y| {
+      let row = &luma[y * 9..y * 9 + 9];
+      (0..8).map(move |x| row[x] < row[x + 1])
+    }))
+  }
+
+  /// DCT 哈希：32x32 亮度做二维 DCT，取左上角 8x8 低频系数与中位数比较
+  pub fn phash(&self) -> ImageHash {
+    const N: usize = 32;
+    let luma = self.luma(N as u32, N as u32);
+
+    // 用 libm 而不是平台的 cos，保证各平台上的系数一致，中位数附近的位不会翻转
+    let cos: Vec<f32> = (0..8 * N)
+      .map(|i| {
+        let (k, n) = (i / N, i % N);
+        libm::cosf((2 * n + 1) as f32 * k as f32 * std::f32::consts::PI / (2 * N) as f32)
+      })
+      .collect();
+
+    // 只需要低频部分，先对行再对列做一维 DCT
+    let mut rows = vec![0f32; N * 8];
+    for y in 0..N {
+      for k in 0..8 {
+        rows[y * 8 + k] = (0..N).map(|x| luma[y * N + x] * cos[k * N + x]).sum();
+      }
+    }
+
+    let mut coefficients = [0f32; 64];
+    for k in 0..8 {
+      for u in 0..8 {
+        coefficients[k * 8 + u] = (0..N).map(|y| rows[y * 8 + u] * cos[k * N + y]).sum();
+      }
+    }
+
+    // 直流分量与整体亮度相关，不参与中位数
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let median = sorted[sorted.len() / 2];
+
+    bits(coefficients.iter().map(|&c| c > median))
+  }
+}
+
+fn bits(values: impl Iterator<Item = bool>) -> ImageHash {
+  ImageHash(
+    values
+      .take(64)
+      .fold(0u64, |hash, bit| (hash << 1) | bit as u64),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // 中间一个亮块的图，offset 控制亮块位置
+  fn block(width: u32, height: u32, offset: u32) -> Image {
+    let mut rgba = Vec::new();
+    for y in 0..height {
+      for x in 0..width {
+        let inside =
+          (offset..offset + width / 2).contains(&x) && (height / 4..height * 3 / 4).contains(&y);
+        let v = if inside { 230 } else { (x + y) as u8 / 4 };
+        rgba.extend_from_slice(&[v, v, v, 255]);
+      }
+    }
+    Image::new(width, height, rgba)
+  }
+
+  #[test]
+  fn resized_copy_is_similar() {
+    let image = block(128, 128, 0);
+    let small = image.resize(64, 64, Filter::Bicubic);
+    let other = block(128, 128, 64);
+
+    for hash in [Image::ahash, Image::dhash, Image::phash] {
+      assert!(hash(&image).is_similar(&hash(&small), 4));
+      assert!(!hash(&image).is_similar(&hash(&other), 10));
+    }
+  }
+
+  #[test]
+  fn hashes_match_golden_values() {
+    // 哈希会持久化用于去重，任何平台上同一张图都必须得到同样的值
+    let image = block(64, 48, 8);
+    assert_eq!(image.ahash(), ImageHash(0x0030_f8fc_fcfc_3800));
+    assert_eq!(image.dhash(), ImageHash(0xffd1_c1c1_c1c1_d1ff));
+    assert_eq!(image.phash(), ImageHash(0xc149_3e3e_3e59_c1c9));
+  }
+
+  #[test]
+  fn group_is_transitive() {
+    let hashes = [
+      ImageHash(0b0000),
+      ImageHash(0b0011),
+      ImageHash(0b1111),
+      ImageHash(u64::MAX),
+    ];
+    assert_eq!(ImageHash::group(&hashes, 2), vec![vec![0, 1, 2], vec![3]]);
+  }
+}
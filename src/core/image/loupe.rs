@@ -0,0 +1,197 @@
+use ab_glyph::FontArc;
+
+use super::watermark::render_text;
+use super::{BlendMode, Color, Image, Rect};
+
+/// 放大镜底部的颜色、坐标信息
+#[derive(Clone)]
+pub struct Readout {
+  pub font: FontArc,
+  pub size: f32,
+  pub color: Color,
+  pub background: Color,
+}
+
+/// 放大镜参数
+#[derive(Clone)]
+pub struct Loupe {
+  /// 每个源像素放大后的边长
+  pub zoom: u32,
+  /// 放大区域的期望边长，实际边长会调整为 zoom 的奇数倍，保证中心像素居中
+  pub size: u32,
+  /// 像素网格线颜色，None 时不画
+  pub grid: Option<Color>,
+  /// 十字准线颜色
+  pub crosshair: Color,
+  /// 图像以外的区域用这个颜色填充
+  pub outside: Color,
+  pub readout: Option<Readout>,
+}
+
+impl Default for Loupe {
+  fn default() -> Self {
+    Loupe {
+      zoom: 8,
+      size: 120,
+      grid: Some(Color::new(0, 0, 0, 40)),
+      crosshair: Color::new(0, 174, 255, 255),
+      outside: Color::rgb(32, 32, 32),
+      readout: None,
+    }
+  }
+}
+
+impl Image {
+  /// 以 (x, y) 为中心最近邻放大，画上网格和十字准线，可选在底部显示坐标和颜色
+  pub fn loupe(&self, x: i32, y: i32, options: &Loupe) -> Image {
+    let zoom = options.zoom.max(1);
+    // 源像素个数取奇数
+    let count = (options.size / zoom).max(1) | 1;
+    let side = count * zoom;
+    let half = (count / 2) as i32;
+
+    let outside = options.outside.to_array();
+    let mut rgba = Vec::with_capacity(side as usize * side as usize * 4);
+    for oy in 0..side {
+      let sy = y - half + (oy / zoom) as i32;
+      for ox in 0..side {
+        let sx = x - half + (ox / zoom) as i32;
+        let pixel = match (sx, sy) {
+          (sx, sy) if sx >= 0 && sy >= 0 => self.pixel(sx as u32, sy as u32),
+          _ => None,
+        };
+        rgba.extend_from_slice(&pixel.map(|p| p.to_array()).unwrap_or(outside));
+      }
+    }
+    let mut zoomed = Image::new(side, side, rgba);
+
+    if let Some(grid) = options.grid {
+      for i in 1..count {
+        zoomed.fill_rect(Rect::new((i * zoom) as i32, 0, 1, side), grid);
+        zoomed.fill_rect(Rect::new(0, (i * zoom) as i32, side, 1), grid);
+      }
+    }
+
+    // 十字准线：中心行列半透明高亮，中心像素描边
+    let center = (half as u32 * zoom) as i32;
+    let mut band = options.crosshair;
+    band.a /= 4;
+    zoomed.fill_rect(Rect::new(center, 0, zoom, center as u32), band);
+    zoomed.fill_rect(
+      Rect::new(center, center + zoom as i32, zoom, center as u32),
+      band,
+    );
+    zoomed.fill_rect(Rect::new(0, center, center as u32, zoom), band);
+    zoomed.fill_rect(
+      Rect::new(center + zoom as i32, center, center as u32, zoom),
+      band,
+    );
+    zoomed.stroke_rect(Rect::new(center, center, zoom, zoom), options.crosshair);
+    zoomed.stroke_rect(zoomed.bounds(), options.crosshair);
+
+    let readout = match &options.readout {
+      Some(readout) => readout,
+      None => return zoomed,
+    };
+
+    let color = match (x, y) {
+      (x, y) if x >= 0 && y >= 0 => self.pixel(x as u32, y as u32),
+      _ => None,
+    };
+    let text = match color {
+      Some(color) => format!("({x}, {y})\n{}", color.to_hex()),
+      None => format!("({x}, {y})"),
+    };
+    let label = render_text(&text, &readout.font, readout.size, readout.color.to_array());
+
+    // 底部信息栏：左侧色块，右侧文字
+    let padding = 4;
+    let swatch = label.height.max(1);
+    let bar_height = label.height + padding * 2;
+    let width = side.max(swatch + label.width + padding * 3);
+    let background = readout.background.to_array();
+    let mut result = Image::new(
+      width,
+      side + bar_height,
+      background.repeat(width as usize * (side + bar_height) as usize),
+    );
+
+    result.copy_from(&zoomed.view(), 0, 0);
+    if let Some(color) = color {
+      result.fill_rect(
+        Rect::new(padding as i32, (side + padding) as i32, swatch, swatch),
+        color,
+      );
+    }
+    result.blend(
+      &label.view(),
+      (swatch + padding * 2) as i32,
+      (side + padding) as i32,
+      BlendMode::Over,
+      1.0,
+    );
+
+    result
+  }
+
+  /// 用 color 覆盖 rect，color 的 alpha 按 source-over 混合
+  pub fn fill_rect(&mut self, rect: Rect, color: Color) {
+    let patch = Image::new(
+      rect.width,
+      rect.height,
+      color
+        .to_array()
+        .repeat(rect.width as usize * rect.height as usize),
+    );
+    self.blend(&patch.view(), rect.x, rect.y, BlendMode::Over, 1.0);
+  }
+
+  /// 画 1 像素宽的矩形边框，边框在 rect 内侧
+  pub fn stroke_rect(&mut self, rect: Rect, color: Color) {
+    if rect.is_empty() {
+      return;
+    }
+
+    self.fill_rect(Rect::new(rect.x, rect.y, rect.width, 1), color);
+    self.fill_rect(Rect::new(rect.x, rect.bottom() - 1, rect.width, 1), color);
+    if rect.height > 2 {
+      self.fill_rect(Rect::new(rect.x, rect.y + 1, 1, rect.height - 2), color);
+      self.fill_rect(
+        Rect::new(rect.right() - 1, rect.y + 1, 1, rect.height - 2),
+        color,
+      );
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn loupe_magnifies_around_center() {
+    let mut rgba = Vec::new();
+    for y in 0..10u8 {
+      for x in 0..10u8 {
+        rgba.extend_from_slice(&[x * 20, y * 20, 0, 255]);
+      }
+    }
+    let image = Image::new(10, 10, rgba);
+    let options = Loupe {
+      zoom: 4,
+      size: 20,
+      grid: None,
+      ..Loupe::default()
+    };
+    let loupe = image.loupe(0, 5, &options);
+
+    // 20 / 4 = 5 个源像素，中心是 (0, 5)
+    assert_eq!((loupe.width(), loupe.height()), (20, 20));
+    assert_eq!(loupe.pixel(9, 9), Some(Color::rgb(0, 100, 0)));
+    assert_eq!(loupe.pixel(13, 1), Some(Color::rgb(20, 60, 0)));
+    // 左侧超出图像
+    assert_eq!(loupe.pixel(1, 1), Some(options.outside));
+    // 中心像素描边
+    assert_eq!(loupe.pixel(8, 8), Some(options.crosshair));
+  }
+}
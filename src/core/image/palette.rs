@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+
+use png::{BitDepth, ColorType, Encoder, EncodingError};
+
+use super::{Color, Image};
+
+/// 调色板中的一个颜色及其占比
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Swatch {
+  pub color: Color,
+  /// 最接近该颜色的像素数
+  pub pixels: usize,
+  /// 占参与统计像素的百分比，0 ~ 100
+  pub coverage: f32,
+}
+
+/// 直方图中的一格，每个通道取高 5 位
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+  sum: [u64; 4],
+  count: u64,
+}
+
+impl Bucket {
+  fn color(&self) -> [u8; 4] {
+    self.sum.map(|v| ((v + self.count / 2) / self.count) as u8)
+  }
+}
+
+/// 中位切分的一个盒子
+struct Cube {
+  buckets: Vec<Bucket>,
+}
+
+impl Cube {
+  fn count(&self) -> u64 {
+    self.buckets.iter().map(|b| b.count).sum()
+  }
+
+  /// 跨度最大的通道及跨度
+  fn widest(&self) -> (usize, u8) {
+    (0..4)
+      .map(|c| {
+        let (min, max) = self
+          .buckets
+          .iter()
+          .map(|b| b.color()[c])
+          .fold((u8::MAX, 0), |(min, max), v| (min.min(v), max.max(v)));
+        (c, max.saturating_sub(min))
+      })
+      .max_by_key(|&(c, range)| (range, std::cmp::Reverse(c)))
+      .unwrap_or((0, 0))
+  }
+
+  /// 在跨度最大的通道上按像素数的中位数一分为二
+  fn split(mut self) -> (Cube, Cube) {
+    let (channel, _) = self.widest();
+    self.buckets.sort_by_key(|b| b.color()[channel]);
+
+    let half = self.count() / 2;
+    let mut acc = 0;
+    let mut at = 1;
+    for (i, bucket) in self.buckets.iter().enumerate() {
+      acc += bucket.count;
+      if acc >= half {
+        at = (i + 1).clamp(1, self.buckets.len() - 1);
+        break;
+      }
+    }
+
+    let rest = self.buckets.split_off(at);
+    (self, Cube { buckets: rest })
+  }
+
+  fn color(&self) -> Color {
+    let mut sum = [0u64; 4];
+    let mut count = 0;
+    for bucket in &self.buckets {
+      for (s, v) in sum.iter_mut().zip(bucket.sum) {
+        *s += v;
+      }
+      count += bucket.count;
+    }
+    sum.map(|v| ((v + count / 2) / count) as u8).into()
+  }
+}
+
+fn key(p: &[u8]) -> u32 {
+  (p[0] as u32 >> 3) << 15 | (p[1] as u32 >> 3) << 10 | (p[2] as u32 >> 3) << 5 | (p[3] as u32 >> 3)
+}
+
+fn distance(a: &[u8; 4], b: &[u8]) -> u32 {
+  a.iter()
+    .zip(b)
+    .map(|(x, y)| {
+      let d = *x as i32 - *y as i32;
+      (d * d) as u32
+    })
+    .sum()
+}
+
+impl Image {
+  /// 中位切分量化，返回最多 max_colors 个颜色
+  ///
+  /// include_transparent 为 false 时忽略完全透明的像素
+  pub fn median_cut(&self, max_colors: usize, include_transparent: bool) -> Vec<Color> {
+    let mut histogram: HashMap<u32, Bucket> = HashMap::new();
+    for p in self.rgba.chunks_exact(4) {
+      if p[3] == 0 && !include_transparent {
+        continue;
+      }
+
+      let bucket = histogram.entry(key(p)).or_insert(Bucket {
+        sum: [0; 4],
+        count: 0,
+      });
+      for (s, v) in bucket.sum.iter_mut().zip(p) {
+        *s += *v as u64;
+      }
+      bucket.count += 1;
+    }
+
+    if histogram.is_empty() || max_colors == 0 {
+      return Vec::new();
+    }
+
+    // HashMap 的遍历顺序不固定，排序后结果才稳定
+    let mut buckets: Vec<(u32, Bucket)> = histogram.into_iter().collect();
+    buckets.sort_by_key(|&(key, _)| key);
+    let mut cubes = vec![Cube {
+      buckets: buckets.into_iter().map(|(_, b)| b).collect(),
+    }];
+
+    while cubes.len() < max_colors {
+      // 优先切分像素多且跨度大的盒子
+      let next = cubes
+        .iter()
+        .enumerate()
+        .filter(|(_, cube)| cube.buckets.len() > 1)
+        .max_by_key(|(i, cube)| (cube.count() * cube.widest().1 as u64, std::cmp::Reverse(*i)))
+        .map(|(i, _)| i);
+
+      match next {
+        Some(i) => {
+          let (a, b) = cubes.swap_remove(i).split();
+          cubes.push(a);
+          cubes.push(b);
+        }
+        None => break,
+      }
+    }
+
+    cubes.iter().map(Cube::color).collect()
+  }
+
+  /// 每个像素在 palette 中最接近的颜色的下标，palette 不能超过 256 个颜色
+  pub fn quantize(&self, palette: &[Color]) -> Vec<u8> {
+    let palette: Vec<[u8; 4]> = palette.iter().copied().map(Color::to_array).collect();
+    let mut cache: HashMap<[u8; 4], u8> = HashMap::new();
+
+    self
+      .rgba
+      .chunks_exact(4)
+      .map(|p| {
+        let rgba = [p[0], p[1], p[2], p[3]];
+        *cache.entry(rgba).or_insert_with(|| {
+          (0..palette.len())
+            .min_by_key(|&i| distance(&palette[i], p))
+            .unwrap_or(0) as u8
+        })
+      })
+      .collect()
+  }
+
+  /// 主色调，按占比从大到小排列，最多 256 个，忽略完全透明的像素
+  pub fn palette(&self, count: usize) -> Vec<Swatch> {
+    let colors = self.median_cut(count.min(256), false);
+    let mut pixels = vec![0usize; colors.len()];
+    let mut total = 0;
+
+    for (p, i) in self.rgba.chunks_exact(4).zip(self.quantize(&colors)) {
+      if p[3] != 0 {
+        pixels[i as usize] += 1;
+        total += 1;
+      }
+    }
+
+    let mut swatches: Vec<Swatch> = colors
+      .into_iter()
+      .zip(pixels)
+      .filter(|&(_, pixels)| pixels > 0)
+      .map(|(color, pixels)| Swatch {
+        color,
+        pixels,
+        coverage: pixels as f32 * 100.0 / total as f32,
+      })
+      .collect();
+    swatches.sort_by_key(|s| std::cmp::Reverse(s.pixels));
+
+    swatches
+  }
+
+  /// 量化到最多 max_colors（不超过 256）个颜色后编码为索引色 PNG
+  pub fn to_indexed_png(&self, max_colors: usize) -> Result<Vec<u8>, EncodingError> {
+    let mut colors = self.median_cut(max_colors.clamp(1, 256), true);
+    if colors.is_empty() {
+      colors.push(Color::default());
+    }
+    let indices = self.quantize(&colors);
+
+    let mut buffer = Vec::new();
+    let mut encoder = Encoder::new(&mut buffer, self.width, self.height);
+    encoder.set_color(ColorType::Indexed);
+    encoder.set_depth(BitDepth::Eight);
+    encoder.set_palette(
+      colors
+        .iter()
+        .flat_map(|c| [c.r, c.g, c.b])
+        .collect::<Vec<u8>>(),
+    );
+    if colors.iter().any(|c| c.a != 255) {
+      encoder.set_trns(colors.iter().map(|c| c.a).collect::<Vec<u8>>());
+    }
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&indices)?;
+    writer.finish()?;
+
+    Ok(buffer)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn two_colors() -> Image {
+    let mut rgba = [200, 30, 30, 255].repeat(75);
+    rgba.extend([20, 40, 220, 255].repeat(25));
+    Image::new(10, 10, rgba)
+  }
+
+  #[test]
+  fn dominant_colors_with_coverage() {
+    let palette = two_colors().palette(4);
+
+    assert_eq!(palette.len(), 2);
+    assert_eq!(palette[0].color, Color::rgb(200, 30, 30));
+    assert_eq!(palette[0].coverage, 75.0);
+    assert_eq!(palette[1].color, Color::rgb(20, 40, 220));
+  }
+
+  #[test]
+  fn indexed_png_round_trip() {
+    let image = two_colors();
+    let png = image.to_indexed_png(16).unwrap();
+
+    let decoder = png::Decoder::new(png.as_slice());
+    let mut reader = decoder.read_info().unwrap();
+    assert_eq!(reader.info().color_type, ColorType::Indexed);
+
+    let mut indices = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut indices).unwrap();
+    assert_ne!(indices[0], indices[99]);
+    assert_eq!(indices[0], indices[74]);
+  }
+}
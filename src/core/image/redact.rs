@@ -0,0 +1,272 @@
+use super::{Image, Rect};
+
+/// 马赛克方块的最小边长，更小的方块保留了太多细节，文字仍然可以被猜出来
+pub const MIN_PIXELATE_BLOCK: u32 = 8;
+
+/// 需要打码的区域
+#[derive(Debug, Clone, PartialEq)]
+pub enum Region {
+  Rect(Rect),
+  /// 多边形顶点（像素坐标），按奇偶规则判断内外
+  Polygon(Vec<(f32, f32)>),
+}
+
+impl Region {
+  /// 外接矩形
+  pub fn bounds(&self) -> Rect {
+    match self {
+      Region::Rect(rect) => *rect,
+      Region::Polygon(points) => {
+        if points.is_empty() {
+          return Rect::default();
+        }
+
+        let (mut left, mut top) = (f32::MAX, f32::MAX);
+        let (mut right, mut bottom) = (f32::MIN, f32::MIN);
+        for &(x, y) in points {
+          left = left.min(x);
+          top = top.min(y);
+          right = right.max(x);
+          bottom = bottom.max(y);
+        }
+
+        let x = left.floor() as i32;
+        let y = top.floor() as i32;
+        Rect::new(
+          x,
+          y,
+          (right.ceil() as i32 - x).max(0) as u32,
+          (bottom.ceil() as i32 - y).max(0) as u32,
+        )
+      }
+    }
+  }
+
+  /// 以像素中心判断 (x, y) 是否在区域内
+  pub fn contains(&self, x: i32, y: i32) -> bool {
+    match self {
+      Region::Rect(rect) => rect.contains(x, y),
+      Region::Polygon(points) => {
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+        let mut inside = false;
+
+        for i in 0..points.len() {
+          let (x1, y1) = points[i];
+          let (x2, y2) = points[(i + 1) % points.len()];
+          if (y1 > py) != (y2 > py) && px < x1 + (py - y1) / (y2 - y1) * (x2 - x1) {
+            inside = !inside;
+          }
+        }
+
+        inside
+      }
+    }
+  }
+}
+
+/// 打码方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Redaction {
+  /// 马赛克，block 为方块边长，小于 `MIN_PIXELATE_BLOCK` 时按最小值处理
+  Pixelate { block: u32 },
+  /// 高斯模糊，radius 为卷积核半径
+  Blur { radius: u32 },
+  /// 纯色填充
+  Fill([u8; 4]),
+}
+
+impl Image {
+  /// 对区域打码，区域内原有像素会被直接覆盖，导出的 PNG 中无法还原
+  pub fn redact(&mut self, region: &Region, redaction: Redaction) {
+    let bounds = match region.bounds().intersect(&self.bounds()) {
+      Some(bounds) => bounds,
+      None => return,
+    };
+    let area = match self.sub_view(bounds) {
+      Some(view) => view,
+      None => return,
+    };
+
+    let patch = match redaction {
+      Redaction::Pixelate { block } => pixelate(&area.to_image(), block.max(MIN_PIXELATE_BLOCK)),
+      Redaction::Blur { radius } => {
+        // 单纯的高斯模糊可以被反卷积部分还原，先打一层马赛克丢掉细节再模糊
+        let block = (radius / 2).max(2);
+        blur(&pixelate(&area.to_image(), block), radius)
+      }
+      Redaction::Fill(color) => {
+        let size = bounds.width as usize * bounds.height as usize;
+        Image::new(bounds.width, bounds.height, color.repeat(size))
+      }
+    };
+
+    let stride = self.width as usize * 4;
+    for y in 0..bounds.height {
+      for x in 0..bounds.width {
+        let (gx, gy) = (bounds.x + x as i32, bounds.y + y as i32);
+        if !region.contains(gx, gy) {
+          continue;
+        }
+
+        let dst = gy as usize * stride + gx as usize * 4;
+        self.rgba[dst..dst + 4].copy_from_slice(&patch.view().pixel(x, y));
+      }
+    }
+  }
+
+  /// 高斯模糊，返回新图。只用于视觉效果，不能用来遮挡敏感信息，打码请用 `redact`
+  pub fn blur(&self, radius: u32) -> Image {
+    blur(self, radius)
+  }
+}
+
+/// 每个方块内所有像素替换为方块的平均色
+fn pixelate(image: &Image, block: u32) -> Image {
+  let block = block.max(1);
+  let mut result = image.clone();
+  let stride = image.width as usize * 4;
+
+  for by in (0..image.height).step_by(block as usize) {
+    for bx in (0..image.width).step_by(block as usize) {
+      let bw = block.min(image.width - bx);
+      let bh = block.min(image.height - by);
+
+      let mut sum = [0u64; 4];
+      for y in by..by + bh {
+        for x in bx..bx + bw {
+          let i = y as usize * stride + x as usize * 4;
+          for (s, v) in sum.iter_mut().zip(&image.rgba[i..i + 4]) {
+            *s += *v as u64;
+          }
+        }
+      }
+
+      let count = (bw * bh) as u64;
+      let average = sum.map(|v| ((v + count / 2) / count) as u8);
+      for y in by..by + bh {
+        for x in bx..bx + bw {
+          let i = y as usize * stride + x as usize * 4;
+          result.rgba[i..i + 4].copy_from_slice(&average);
+        }
+      }
+    }
+  }
+
+  result
+}
+
+/// 可分离的高斯模糊，边缘像素向外延伸
+pub(super) fn blur(image: &Image, radius: u32) -> Image {
+  if radius == 0 || image.width == 0 || image.height == 0 {
+    return image.clone();
+  }
+
+  let sigma = (radius as f32 / 3.0).max(0.5);
+  let kernel: Vec<f32> = (-(radius as i32)..=radius as i32)
+    .map(|i| libm::expf(-((i * i) as f32) / (2.0 * sigma * sigma)))
+    .collect();
+  let total: f32 = kernel.iter().sum();
+  let kernel: Vec<f32> = kernel.iter().map(|k| k / total).collect();
+
+  let (width, height) = (image.width as i32, image.height as i32);
+  let pass = |src: &[u8], horizontal: bool| -> Vec<u8> {
+    let mut dst = vec![0u8; src.len()];
+    for y in 0..height {
+      for x in 0..width {
+        let mut sum = [0f32; 4];
+        for (k, w) in kernel.iter().enumerate() {
+          let offset = k as i32 - radius as i32;
+          let (sx, sy) = if horizontal {
+            ((x + offset).clamp(0, width - 1), y)
+          } else {
+            (x, (y + offset).clamp(0, height - 1))
+          };
+          let i = (sy * width + sx) as usize * 4;
+          for c in 0..4 {
+            sum[c] += src[i + c] as f32 * w;
+          }
+        }
+
+        let i = (y * width + x) as usize * 4;
+        for c in 0..4 {
+          dst[i + c] = sum[c].round().clamp(0.0, 255.0) as u8;
+        }
+      }
+    }
+    dst
+  };
+
+  let horizontal = pass(&image.rgba, true);
+  Image::new(image.width, image.height, pass(&horizontal, false))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn noise(width: u32, height: u32) -> Image {
+    let rgba = (0..width * height)
+      .flat_map(|i| {
+        let v = (i * 37 % 251) as u8;
+        [v, 255 - v, v / 2, 255]
+      })
+      .collect();
+    Image::new(width, height, rgba)
+  }
+
+  #[test]
+  fn fill_only_touches_region() {
+    let mut image = noise(6, 6);
+    let before = image.view().pixel(0, 0);
+    image.redact(
+      &Region::Rect(Rect::new(1, 1, 2, 2)),
+      Redaction::Fill([0, 0, 0, 255]),
+    );
+
+    assert_eq!(image.view().pixel(1, 1), [0, 0, 0, 255]);
+    assert_eq!(image.view().pixel(2, 2), [0, 0, 0, 255]);
+    assert_eq!(image.view().pixel(0, 0), before);
+    assert_ne!(image.view().pixel(3, 3), [0, 0, 0, 255]);
+  }
+
+  #[test]
+  fn pixelate_makes_blocks_uniform() {
+    let mut image = noise(16, 16);
+    image.redact(
+      &Region::Rect(image.bounds()),
+      Redaction::Pixelate { block: 8 },
+    );
+
+    let first = image.view().pixel(0, 0);
+    for y in 0..8 {
+      for x in 0..8 {
+        assert_eq!(image.view().pixel(x, y), first);
+      }
+    }
+    assert_ne!(image.view().pixel(8, 0), first);
+  }
+
+  #[test]
+  fn tiny_blocks_still_destroy_pixels() {
+    let source = noise(8, 8);
+    for block in [0, 1] {
+      let mut image = source.clone();
+      image.redact(&Region::Rect(image.bounds()), Redaction::Pixelate { block });
+
+      // 按最小方块处理，8x8 的区域只剩一种颜色
+      let first = image.view().pixel(0, 0);
+      assert!(image.rgba().chunks_exact(4).all(|p| p == first));
+      assert_ne!(image.rgba(), source.rgba());
+    }
+  }
+
+  #[test]
+  fn polygon_region() {
+    let mut image = noise(10, 10);
+    let triangle = Region::Polygon(vec![(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)]);
+    image.redact(&triangle, Redaction::Fill([1, 2, 3, 255]));
+
+    assert_eq!(image.view().pixel(1, 1), [1, 2, 3, 255]);
+    assert_ne!(image.view().pixel(8, 8), [1, 2, 3, 255]);
+  }
+}
@@ -0,0 +1,196 @@
+use super::Image;
+
+// 分块处理，一块 64x64 像素（16KB）刚好能放进 L1 缓存
+const TILE: u32 = 64;
+
+impl Image {
+  /// 任意角度顺时针旋转，画布扩大到能完整容纳旋转后的图，空白处透明，双线性采样
+  pub fn rotate(&self, degrees: f32) -> Image {
+    if degrees.rem_euclid(360.0) == 0.0 {
+      return self.clone();
+    }
+
+    let (sin, cos) = libm::sincosf(degrees.to_radians());
+    let (w, h) = (self.width as f32, self.height as f32);
+    // 减去一个很小的数，避免 90 度时 cos 的浮点误差让尺寸多出 1
+    let width = (w * cos.abs() + h * sin.abs() - 1e-3).ceil() as u32;
+    let height = (w * sin.abs() + h * cos.abs() - 1e-3).ceil() as u32;
+
+    // 越界视为透明
+    let sample = |x: i32, y: i32| -> [f32; 4] {
+      if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+        return [0.0; 4];
+      }
+      let p = self.view().pixel(x as u32, y as u32);
+      let a = p[3] as f32 / 255.0;
+      [p[0] as f32 * a, p[1] as f32 * a, p[2] as f32 * a, a]
+    };
+
+    let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height {
+      for x in 0..width {
+        let dx = x as f32 + 0.5 - width as f32 / 2.0;
+        let dy = y as f32 + 0.5 - height as f32 / 2.0;
+        let sx = dx * cos + dy * sin + w / 2.0 - 0.5;
+        let sy = -dx * sin + dy * cos + h / 2.0 - 0.5;
+
+        let (x0, y0) = (sx.floor() as i32, sy.floor() as i32);
+        let (fx, fy) = (sx - x0 as f32, sy - y0 as f32);
+        let mut sum = [0f32; 4];
+        for (px, py, weight) in [
+          (x0, y0, (1.0 - fx) * (1.0 - fy)),
+          (x0 + 1, y0, fx * (1.0 - fy)),
+          (x0, y0 + 1, (1.0 - fx) * fy),
+          (x0 + 1, y0 + 1, fx * fy),
+        ] {
+          let p = sample(px, py);
+          for c in 0..4 {
+            sum[c] += p[c] * weight;
+          }
+        }
+
+        if sum[3] <= 0.0 {
+          rgba.extend_from_slice(&[0, 0, 0, 0]);
+        } else {
+          rgba.extend_from_slice(&[
+            (sum[0] / sum[3]).round() as u8,
+            (sum[1] / sum[3]).round() as u8,
+            (sum[2] / sum[3]).round() as u8,
+            (sum[3] * 255.0).round() as u8,
+          ]);
+        }
+      }
+    }
+
+    Image::new(width, height, rgba)
+  }
+
+  /// 上下翻转，原地交换行
+  pub fn flip_vertical(&mut self) {
+    let stride = self.width as usize * 4;
+    let height = self.height as usize;
+
+    for y in 0..height / 2 {
+      let (top, bottom) = self.rgba.split_at_mut((height - 1 - y) * stride);
+      top[y * stride..(y + 1) * stride].swap_with_slice(&mut bottom[..stride]);
+    }
+  }
+
+  /// 左右翻转，原地交换每一行的像素
+  pub fn flip_horizontal(&mut self) {
+    let stride = self.width as usize * 4;
+    if stride == 0 {
+      return;
+    }
+
+    for row in self.rgba.chunks_exact_mut(stride) {
+      let width = row.len() / 4;
+      for x in 0..width / 2 {
+        let (left, right) = row.split_at_mut((width - 1 - x) * 4);
+        left[x * 4..x * 4 + 4].swap_with_slice(&mut right[..4]);
+      }
+    }
+  }
+
+  /// 旋转 180 度，原地完成
+  pub fn rotate180(&mut self) {
+    // 像素顺序整体反转即可，但不能把单个像素内的 RGBA 也反转
+    let pixels = self.rgba.len() / 4;
+    for i in 0..pixels / 2 {
+      let (front, back) = self.rgba.split_at_mut((pixels - 1 - i) * 4);
+      front[i * 4..i * 4 + 4].swap_with_slice(&mut back[..4]);
+    }
+  }
+
+  /// 顺时针旋转 90 度
+  pub fn rotate90(&self) -> Image {
+    let height = self.height;
+    self.remap(self.height, self.width, |x, y| (y, height - 1 - x))
+  }
+
+  /// 顺时针旋转 270 度（即逆时针 90 度）
+  pub fn rotate270(&self) -> Image {
+    let width = self.width;
+    self.remap(self.height, self.width, |x, y| (width - 1 - y, x))
+  }
+
+  /// 沿主对角线转置
+  pub fn transpose(&self) -> Image {
+    self.remap(self.height, self.width, |x, y| (y, x))
+  }
+
+  /// 按目标坐标分块遍历，source 把目标坐标映射到源坐标
+  fn remap<F>(&self, width: u32, height: u32, source: F) -> Image
+  where
+    F: Fn(u32, u32) -> (u32, u32),
+  {
+    let mut rgba = vec![0u8; self.rgba.len()];
+    let src_stride = self.width as usize * 4;
+    let dst_stride = width as usize * 4;
+
+    for tile_y in (0..height).step_by(TILE as usize) {
+      for tile_x in (0..width).step_by(TILE as usize) {
+        for y in tile_y..(tile_y + TILE).min(height) {
+          for x in tile_x..(tile_x + TILE).min(width) {
+            let (sx, sy) = source(x, y);
+            let src = sy as usize * src_stride + sx as usize * 4;
+            let dst = y as usize * dst_stride + x as usize * 4;
+            rgba[dst..dst + 4].copy_from_slice(&self.rgba[src..src + 4]);
+          }
+        }
+      }
+    }
+
+    Image::new(width, height, rgba)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // 3x2 图像，每个像素的 R 通道为其序号
+  fn numbered() -> Image {
+    let rgba = (0..6u8).flat_map(|i| [i, 0, 0, 255]).collect();
+    Image::new(3, 2, rgba)
+  }
+
+  fn reds(image: &Image) -> Vec<u8> {
+    image.rgba().chunks_exact(4).map(|p| p[0]).collect()
+  }
+
+  #[test]
+  fn flips_and_rotate180() {
+    let mut image = numbered();
+    image.flip_vertical();
+    assert_eq!(reds(&image), vec![3, 4, 5, 0, 1, 2]);
+
+    let mut image = numbered();
+    image.flip_horizontal();
+    assert_eq!(reds(&image), vec![2, 1, 0, 5, 4, 3]);
+
+    let mut image = numbered();
+    image.rotate180();
+    assert_eq!(reds(&image), vec![5, 4, 3, 2, 1, 0]);
+  }
+
+  #[test]
+  fn quarter_turns() {
+    let image = numbered();
+
+    let rotated = image.rotate90();
+    assert_eq!((rotated.width(), rotated.height()), (2, 3));
+    assert_eq!(reds(&rotated), vec![3, 0, 4, 1, 5, 2]);
+    assert_eq!(reds(&image.rotate270()), vec![2, 5, 1, 4, 0, 3]);
+    assert_eq!(reds(&image.transpose()), vec![0, 3, 1, 4, 2, 5]);
+  }
+
+  #[test]
+  fn rotate_quarter_turn_keeps_size() {
+    let image = Image::new(4, 2, [0, 255, 0, 255].repeat(8));
+    let rotated = image.rotate(90.0);
+
+    assert_eq!((rotated.width(), rotated.height()), (2, 4));
+    assert_eq!(rotated.view().pixel(1, 2), [0, 255, 0, 255]);
+  }
+}
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use super::{Image, Rect};
+
+fn similar(a: &[u8], b: &[u8], tolerance: u8) -> bool {
+  a.iter().zip(b).all(|(x, y)| x.abs_diff(*y) <= tolerance)
+}
+
+impl Image {
+  fn pixel_at(&self, x: u32, y: u32) -> &[u8] {
+    let i = (y as usize * self.width as usize + x as usize) * 4;
+    &self.rgba[i..i + 4]
+  }
+
+  /// 去掉纯色边框后剩余的区域
+  ///
+  /// 四条边分别处理，每条边以最外侧的颜色为准向内收缩，所以上下左右的边框颜色可以不同。
+  /// 多层不同颜色的边框可以多次调用 `trim` 去掉。整张图都是纯色时返回 None
+  pub fn trim_bounds(&self, tolerance: u8) -> Option<Rect> {
+    if self.width == 0 || self.height == 0 {
+      return None;
+    }
+
+    // [x0, x1) * [y0, y1) 内的像素是否都与 color 相同
+    let uniform = |x0: u32, x1: u32, y0: u32, y1: u32, color: &[u8]| {
+      (y0..y1).all(|y| (x0..x1).all(|x| similar(self.pixel_at(x, y), color, tolerance)))
+    };
+
+    let color = self.pixel_at(0, 0);
+    let mut top = 0;
+    while top < self.height && uniform(0, self.width, top, top + 1, color) {
+      top += 1;
+    }
+    if top == self.height {
+      return None;
+    }
+
+    let color = self.pixel_at(0, self.height - 1);
+    let mut bottom = self.height;
+    while bottom > top && uniform(0, self.width, bottom - 1, bottom, color) {
+      bottom -= 1;
+    }
+
+    let color = self.pixel_at(0, top);
+    let mut left = 0;
+    while left < self.width && uniform(left, left + 1, top, bottom, color) {
+      left += 1;
+    }
+
+    let color = self.pixel_at(self.width - 1, top);
+    let mut right = self.width;
+    while right > left && uniform(right - 1, right, top, bottom, color) {
+      right -= 1;
+    }
+
+    if left >= right {
+      return None;
+    }
+
+    Some(Rect::new(
+      left as i32,
+      top as i32,
+      right - left,
+      bottom - top,
+    ))
+  }
+
+  /// 裁掉纯色边框，整张图都是纯色时原样返回
+  pub fn trim(&self, tolerance: u8) -> Image {
+    match self.trim_bounds(tolerance).and_then(|rect| self.crop(rect)) {
+      Some(image) => image,
+      None => self.clone(),
+    }
+  }
+
+  /// 边框上出现最多的颜色，作为背景色
+  pub fn border_color(&self) -> Option<[u8; 4]> {
+    if self.width == 0 || self.height == 0 {
+      return None;
+    }
+
+    let mut counts: HashMap<[u8; 4], usize> = HashMap::new();
+    let mut count = |x: u32, y: u32| {
+      let p = self.pixel_at(x, y);
+      *counts.entry([p[0], p[1], p[2], p[3]]).or_default() += 1;
+    };
+
+    for x in 0..self.width {
+      count(x, 0);
+      count(x, self.height - 1);
+    }
+    for y in 0..self.height {
+      count(0, y);
+      count(self.width - 1, y);
+    }
+
+    // 数量相同时取颜色值较小的，保证结果稳定
+    counts
+      .into_iter()
+      .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+      .map(|(color, _)| color)
+  }
+
+  /// 与背景色不同的像素的外接矩形，背景色取边框上最多的颜色
+  ///
+  /// 与 `trim_bounds` 不同，它会跳过边框上零星的杂色，适合窗口截图外围带桌面背景的情况
+  pub fn content_bounds(&self, tolerance: u8) -> Option<Rect> {
+    let background = self.border_color()?;
+
+    let (mut left, mut top) = (u32::MAX, u32::MAX);
+    let (mut right, mut bottom) = (0, 0);
+    for y in 0..self.height {
+      for x in 0..self.width {
+        if !similar(self.pixel_at(x, y), &background, tolerance) {
+          left = left.min(x);
+          top = top.min(y);
+          right = right.max(x + 1);
+          bottom = bottom.max(y + 1);
+        }
+      }
+    }
+
+    if left >= right {
+      return None;
+    }
+
+    Some(Rect::new(
+      left as i32,
+      top as i32,
+      right - left,
+      bottom - top,
+    ))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn trim_borders_of_different_colors() {
+    // 上边 2 行红色，其余边框白色，中间 2x2 黑色
+    let mut image = Image::new(6, 5, [255, 255, 255, 255].repeat(30));
+    image.copy_from(&Image::new(6, 2, [255, 0, 0, 255].repeat(12)).view(), 0, 0);
+    image.copy_from(&Image::new(2, 2, [0, 0, 0, 255].repeat(4)).view(), 2, 2);
+
+    assert_eq!(image.trim_bounds(0), Some(Rect::new(2, 2, 2, 2)));
+    assert_eq!(image.trim(0).width(), 2);
+  }
+
+  #[test]
+  fn content_bounds_ignores_background() {
+    let mut image = Image::new(8, 8, [10, 20, 30, 255].repeat(64));
+    image.copy_from(&Image::new(1, 1, vec![12, 20, 30, 255]).view(), 0, 0);
+    image.copy_from(&Image::new(3, 2, [0, 0, 0, 255].repeat(6)).view(), 4, 5);
+
+    assert_eq!(image.content_bounds(2), Some(Rect::new(4, 5, 3, 2)));
+    assert_eq!(image.content_bounds(0), Some(Rect::new(0, 0, 7, 7)));
+    assert_eq!(Image::new(2, 2, vec![1; 16]).content_bounds(0), None);
+  }
+}
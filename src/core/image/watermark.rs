@@ -0,0 +1,246 @@
+use ab_glyph::{point, Font, FontArc, PxScale, ScaleFont};
+
+use super::{BlendMode, Image};
+
+/// 水印内容
+#[derive(Clone)]
+pub enum Stamp {
+  /// 文字，例如用户名和时间戳，支持用 `\n` 换行
+  Text {
+    text: String,
+    font: FontArc,
+    size: f32,
+    color: [u8; 4],
+  },
+  /// 图片，例如公司 logo
+  Logo(Image),
+}
+
+/// 水印位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Position {
+  TopLeft,
+  TopRight,
+  BottomLeft,
+  BottomRight,
+  Center,
+  /// 水印左上角的坐标
+  Custom(i32, i32),
+}
+
+/// 平铺时相邻水印之间的间距
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tile {
+  pub spacing_x: u32,
+  pub spacing_y: u32,
+}
+
+#[derive(Clone)]
+pub struct Watermark {
+  pub stamp: Stamp,
+  pub position: Position,
+  /// 与图像边缘的距离，Custom 位置时无效
+  pub margin: u32,
+  /// 整体不透明度，0.0 ~ 1.0
+  pub opacity: f32,
+  /// 顺时针旋转角度
+  pub rotation: f32,
+  /// 设置后忽略 position，铺满整张图
+  pub tile: Option<Tile>,
+}
+
+/// 把文字渲染成透明背景的图
+pub(crate) fn render_text(text: &str, font: &FontArc, size: f32, color: [u8; 4]) -> Image {
+  let scale = PxScale::from(size);
+  let scaled = font.as_scaled(scale);
+  let line_height = scaled.height() + scaled.line_gap();
+
+  let mut glyphs = Vec::new();
+  let mut width = 0f32;
+  let lines: Vec<&str> = text.lines().collect();
+  for (row, line) in lines.iter().enumerate() {
+    let baseline = scaled.ascent() + row as f32 * line_height;
+    let mut caret = 0f32;
+    let mut previous = None;
+
+    for c in line.chars() {
+      let id = scaled.glyph_id(c);
+      if let Some(previous) = previous {
+        caret += scaled.kern(previous, id);
+      }
+      glyphs.push(id.with_scale_and_position(scale, point(caret, baseline)));
+      caret += scaled.h_advance(id);
+      previous = Some(id);
+    }
+    width = width.max(caret);
+  }
+
+  let width = width.ceil() as u32;
+  let height = match lines.len() {
+    0 => 0,
+    n => (line_height * (n - 1) as f32 + scaled.height()).ceil() as u32,
+  };
+
+  let mut coverage = vec![0f32; width as usize * height as usize];
+  for glyph in glyphs {
+    if let Some(outlined) = font.outline_glyph(glyph) {
+      let bounds = outlined.px_bounds();
+      outlined.draw(|x, y, c| {
+        let px = bounds.min.x as i32 + x as i32;
+        let py = bounds.min.y as i32 + y as i32;
+        if px >= 0 && py >= 0 && (px as u32) < width && (py as u32) < height {
+          let i = py as usize * width as usize + px as usize;
+          coverage[i] = (coverage[i] + c).min(1.0);
+        }
+      });
+    }
+  }
+
+  let rgba = coverage
+    .iter()
+    .flat_map(|c| {
+      let a = (color[3] as f32 * c).round() as u8;
+      [color[0], color[1], color[2], a]
+    })
+    .collect();
+
+  Image::new(width, height, rgba)
+}
+
+impl Image {
+  /// 添加水印，导出 PNG 前调用
+  pub fn watermark(&mut self, watermark: &Watermark) {
+    let stamp = match &watermark.stamp {
+      Stamp::Text {
+        text,
+        font,
+        size,
+        color,
+      } => render_text(text, font, *size, *color),
+      Stamp::Logo(image) => image.clone(),
+    };
+    let stamp = stamp.rotate(watermark.rotation);
+    let opacity = watermark.opacity.clamp(0.0, 1.0);
+
+    if stamp.width == 0 || stamp.height == 0 {
+      return;
+    }
+
+    if let Some(tile) = watermark.tile {
+      let step_x = (stamp.width + tile.spacing_x) as usize;
+      let step_y = (stamp.height + tile.spacing_y) as usize;
+      for y in (0..self.height).step_by(step_y) {
+        for x in (0..self.width).step_by(step_x) {
+          self.blend(&stamp.view(), x as i32, y as i32, BlendMode::Over, opacity);
+        }
+      }
+      return;
+    }
+
+    let margin = watermark.margin as i32;
+    let right = self.width as i32 - stamp.width as i32 - margin;
+    let bottom = self.height as i32 - stamp.height as i32 - margin;
+    let (x, y) = match watermark.position {
+      Position::TopLeft => (margin, margin),
+      Position::TopRight => (right, margin),
+      Position::BottomLeft => (margin, bottom),
+      Position::BottomRight => (right, bottom),
+      Position::Center => (
+        (self.width as i32 - stamp.width as i32) / 2,
+        (self.height as i32 - stamp.height as i32) / 2,
+      ),
+      Position::Custom(x, y) => (x, y),
+    };
+
+    self.blend(&stamp.view(), x, y, BlendMode::Over, opacity);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::core::image::Rect;
+
+  fn logo_watermark(position: Position) -> Watermark {
+    Watermark {
+      stamp: Stamp::Logo(Image::new(2, 2, [255, 0, 0, 255].repeat(4))),
+      position,
+      margin: 1,
+      opacity: 1.0,
+      rotation: 0.0,
+      tile: None,
+    }
+  }
+
+  #[test]
+  fn logo_at_bottom_right() {
+    let mut image = Image::new(8, 8, [0, 0, 0, 255].repeat(64));
+    image.watermark(&logo_watermark(Position::BottomRight));
+
+    assert_eq!(image.view().pixel(6, 6), [255, 0, 0, 255]);
+    assert_eq!(image.view().pixel(5, 5), [255, 0, 0, 255]);
+    assert_eq!(image.view().pixel(7, 7), [0, 0, 0, 255]);
+  }
+
+  #[test]
+  fn tiled_with_opacity() {
+    let mut image = Image::new(8, 8, [0, 0, 0, 255].repeat(64));
+    let mut watermark = logo_watermark(Position::TopLeft);
+    watermark.opacity = 0.5;
+    watermark.tile = Some(Tile {
+      spacing_x: 2,
+      spacing_y: 2,
+    });
+    image.watermark(&watermark);
+
+    assert_eq!(image.view().pixel(0, 0), [128, 0, 0, 255]);
+    assert_eq!(image.view().pixel(4, 4), [128, 0, 0, 255]);
+    assert_eq!(image.view().pixel(2, 2), [0, 0, 0, 255]);
+  }
+
+  #[test]
+  fn text_stamp_placement_and_opacity() {
+    let font =
+      FontArc::try_from_slice(include_bytes!("../../../assets/fonts/DejaVuSans.ttf")).unwrap();
+    let background = [0, 0, 255, 255];
+    let mut image = Image::new(120, 48, background.repeat(120 * 48));
+    let watermark = Watermark {
+      stamp: Stamp::Text {
+        text: "user 2024".to_string(),
+        font: font.clone(),
+        size: 20.0,
+        color: [255, 255, 255, 255],
+      },
+      position: Position::BottomRight,
+      margin: 4,
+      opacity: 0.5,
+      rotation: 0.0,
+      tile: None,
+    };
+    image.watermark(&watermark);
+
+    // 文字贴着右下角，与边缘保持 margin
+    let stamp = render_text("user 2024", &font, 20.0, [255, 255, 255, 255]);
+    let x = 120 - stamp.width() - 4;
+    let y = 48 - stamp.height() - 4;
+    let area = Rect::new(x as i32, y as i32, stamp.width(), stamp.height());
+
+    let mut inked = 0;
+    for py in 0..image.height() {
+      for px in 0..image.width() {
+        let pixel = image.view().pixel(px, py);
+        if !area.contains(px as i32, py as i32) {
+          assert_eq!(pixel, background, "({px}, {py}) is outside the stamp");
+          continue;
+        }
+
+        // 完全覆盖的笔画按 0.5 的不透明度与底色混合
+        if stamp.view().pixel(px - x, py - y)[3] == 255 {
+          assert_eq!(pixel, [128, 128, 255, 255]);
+          inked += 1;
+        }
+      }
+    }
+    assert!(inked > 0);
+  }
+}
@@ -1,5 +1,7 @@
+pub mod compare;
 pub mod core;
-mod image;
+pub mod image;
+pub mod picker;
 
 use crate::core::image::Image;
 
@@ -44,4 +46,8 @@ impl Screen {
     pub fn capture(&self) -> Result<Image, E> {
         capture_screen(&self.display_info)
     }
+
+    pub fn capture_area(&self, x: i32, y: i32, width: u32, height: u32) -> Result<Image, E> {
+        capture_screen_area(&self.display_info, x, y, width, height)
+    }
 }
@@ -0,0 +1,81 @@
+use anyhow::{anyhow, Result};
+
+use crate::core::image::{Color, Rect};
+use crate::core::Screen;
+
+/// 取平均色时允许的最大半径，避免一次截取过大的区域
+pub const MAX_SAMPLE_RADIUS: u32 = 64;
+
+/// 从屏幕上实时取色
+pub struct ColorPicker;
+
+impl ColorPicker {
+    /// 全局坐标 (x, y) 处的颜色
+    pub fn sample(x: i32, y: i32) -> Result<Color> {
+        Self::sample_average(x, y, 0)
+    }
+
+    /// 以全局坐标 (x, y) 为中心、边长 radius * 2 + 1 的正方形内的平均色
+    ///
+    /// radius 超过 `MAX_SAMPLE_RADIUS` 时按最大值处理，超出屏幕的部分不参与平均
+    pub fn sample_average(x: i32, y: i32, radius: u32) -> Result<Color> {
+        let screen = Screen::from_point(x, y)?;
+        let display_info = screen.display_info;
+        let display = Rect::new(
+            display_info.x,
+            display_info.y,
+            display_info.width,
+            display_info.height,
+        );
+        let area = sample_area(x, y, radius, display)
+            .ok_or_else(|| anyhow!("({x}, {y}) is outside the screen"))?;
+
+        let image = screen.capture_area(area.x, area.y, area.width, area.height)?;
+
+        image
+            .average_color(Rect::new(0, 0, image.width(), image.height()))
+            .ok_or_else(|| anyhow!("Can't sample color at ({x}, {y})"))
+    }
+}
+
+/// 取色区域，坐标相对于 display 左上角（capture_area 使用的坐标），已裁剪到屏幕范围内
+fn sample_area(x: i32, y: i32, radius: u32, display: Rect) -> Option<Rect> {
+    let radius = radius.min(MAX_SAMPLE_RADIUS) as i32;
+    let size = radius as u32 * 2 + 1;
+    let left = x.checked_sub(display.x)?.checked_sub(radius)?;
+    let top = y.checked_sub(display.y)?.checked_sub(radius)?;
+
+    Rect::new(left, top, size, size).intersect(&Rect::new(0, 0, display.width, display.height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_area_is_clamped_to_display() {
+        let display = Rect::new(-1920, 0, 1920, 1080);
+
+        assert_eq!(
+            sample_area(-960, 540, 2, display),
+            Some(Rect::new(958, 538, 5, 5))
+        );
+        // 左上角只剩屏幕内的部分
+        assert_eq!(
+            sample_area(-1920, 0, 2, display),
+            Some(Rect::new(0, 0, 3, 3))
+        );
+        // 半径被限制，不会溢出
+        assert_eq!(
+            sample_area(-1, 1079, u32::MAX, display),
+            Some(Rect::new(
+                1919 - MAX_SAMPLE_RADIUS as i32,
+                1079 - MAX_SAMPLE_RADIUS as i32,
+                MAX_SAMPLE_RADIUS + 1,
+                MAX_SAMPLE_RADIUS + 1,
+            ))
+        );
+        assert_eq!(sample_area(i32::MIN, 0, 2, Rect::new(1, 0, 10, 10)), None);
+        assert_eq!(sample_area(100, 100, 2, Rect::new(0, 0, 10, 10)), None);
+    }
+}
@@ -198,21 +198,16 @@ fn capture(display_id: u32, x: i32, y: i32, width: i32, height: i32) -> Result<I
     );
   }
 
-  // 旋转图像,图像数据是倒置的
-  let mut chunks: Vec<Vec<u8>> = data
-    .chunks(width as usize * 4)
-    .map(|x| x.to_vec())
-    .collect();
-
-  chunks.reverse();
-
-  let image = Image::from_bgra(
-    chunks.concat(),
+  let mut image = Image::from_bgra(
+    data,
     bitmap.bmWidth as u32,
     bitmap.bmHeight as u32,
     bitmap.bmWidthBytes as usize,
   );
 
+  // 旋转图像,图像数据是倒置的
+  image.flip_vertical();
+
   Ok(image)
 }
 
@@ -0,0 +1,459 @@
+use serde::{Deserialize, Serialize};
+
+use crate::core::image::{Color, Image};
+
+/// 标注坐标，以底图左上角为原点的像素坐标
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Point { x, y }
+    }
+}
+
+/// 浮点矩形，width、height 可以为负，表示从右下往左上拖出来的
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct Bounds {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Bounds {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Bounds {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// 由两个对角点构造
+    pub fn from_points(a: Point, b: Point) -> Self {
+        Bounds::new(a.x, a.y, b.x - a.x, b.y - a.y)
+    }
+
+    /// width、height 转为非负
+    pub fn normalized(&self) -> Bounds {
+        Bounds::new(
+            self.x.min(self.x + self.width),
+            self.y.min(self.y + self.height),
+            self.width.abs(),
+            self.height.abs(),
+        )
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new(self.x + self.width / 2.0, self.y + self.height / 2.0)
+    }
+}
+
+/// 描边、填充等样式
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Style {
+    pub stroke: Color,
+    pub fill: Option<Color>,
+    /// 线宽
+    pub width: f32,
+    /// 整体不透明度，0.0 ~ 1.0
+    pub opacity: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            stroke: Color::rgb(255, 59, 48),
+            fill: None,
+            width: 4.0,
+            opacity: 1.0,
+        }
+    }
+}
+
+/// 标注图形
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ShapeKind {
+    Rectangle {
+        bounds: Bounds,
+    },
+    Ellipse {
+        bounds: Bounds,
+    },
+    Line {
+        from: Point,
+        to: Point,
+    },
+    Arrow {
+        from: Point,
+        to: Point,
+        /// 二次贝塞尔控制点，None 为直箭头
+        control: Option<Point>,
+        /// 两端都有箭头
+        double: bool,
+        /// 箭身从尾部到箭头逐渐变粗
+        tapered: bool,
+    },
+    /// 画笔
+    Freehand {
+        points: Vec<Point>,
+    },
+    /// 文字，position 为第一行文字的左上角，颜色取 stroke，背景框取 fill
+    Text {
+        position: Point,
+        text: String,
+        size: f32,
+        /// 自动换行宽度
+        max_width: Option<f32>,
+        /// 描边颜色，宽度取 style.width
+        outline: Option<Color>,
+    },
+    /// 荧光笔，与画笔相同但以正片叠底方式绘制
+    Highlighter {
+        points: Vec<Point>,
+    },
+    /// 气泡对话框，尖角指向 target，文字颜色和边框取 stroke，背景取 fill（默认白色）
+    Callout {
+        bounds: Bounds,
+        target: Point,
+        text: String,
+        size: f32,
+    },
+    /// 聚光灯：压暗或模糊区域外的内容，只影响它下面的图层，
+    /// feather 为边缘羽化宽度，style.opacity 控制效果强度
+    Spotlight {
+        areas: Vec<SpotlightArea>,
+        effect: SpotlightEffect,
+        feather: f32,
+    },
+    /// 带序号的圆形步骤徽标，number 由文档按创建顺序自动编号，删除后重新编号
+    Counter {
+        center: Point,
+        number: u32,
+        radius: f32,
+    },
+}
+
+/// 聚光灯的高亮区域
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpotlightArea {
+    Rectangle(Bounds),
+    Ellipse(Bounds),
+}
+
+impl SpotlightArea {
+    pub fn bounds(&self) -> Bounds {
+        match self {
+            SpotlightArea::Rectangle(bounds) | SpotlightArea::Ellipse(bounds) => *bounds,
+        }
+    }
+}
+
+/// 聚光灯对区域外的处理
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpotlightEffect {
+    /// 压暗，opacity 为叠加黑色的不透明度
+    Dim { opacity: f32 },
+    /// 高斯模糊
+    Blur { radius: f32 },
+}
+
+/// 文档内唯一的图形 id，删除后不会复用
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ShapeId(pub u64);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Shape {
+    pub id: ShapeId,
+    pub kind: ShapeKind,
+    pub style: Style,
+    /// 绕包围盒中心顺时针旋转的角度，只对矩形、椭圆、文字、气泡有效，
+    /// 其他图形旋转时直接改写坐标
+    #[serde(default)]
+    pub rotation: f32,
+}
+
+impl ShapeKind {
+    /// 是否通过 `Shape::rotation` 旋转
+    pub fn is_rotatable(&self) -> bool {
+        matches!(
+            self,
+            ShapeKind::Rectangle { .. }
+                | ShapeKind::Ellipse { .. }
+                | ShapeKind::Text { .. }
+                | ShapeKind::Callout { .. }
+        )
+    }
+
+    /// 平移
+    pub fn translate(&mut self, dx: f32, dy: f32) {
+        let offset = |p: &mut Point| {
+            p.x += dx;
+            p.y += dy;
+        };
+
+        match self {
+            ShapeKind::Rectangle { bounds } | ShapeKind::Ellipse { bounds } => {
+                bounds.x += dx;
+                bounds.y += dy;
+            }
+            ShapeKind::Line { from, to } => {
+                offset(from);
+                offset(to);
+            }
+            ShapeKind::Arrow {
+                from, to, control, ..
+            } => {
+                offset(from);
+                offset(to);
+                control.iter_mut().for_each(offset);
+            }
+            ShapeKind::Callout { bounds, target, .. } => {
+                bounds.x += dx;
+                bounds.y += dy;
+                offset(target);
+            }
+            ShapeKind::Freehand { points } | ShapeKind::Highlighter { points } => {
+                points.iter_mut().for_each(offset);
+            }
+            ShapeKind::Text { position, .. } => offset(position),
+            ShapeKind::Spotlight { areas, .. } => {
+                for area in areas {
+                    match area {
+                        SpotlightArea::Rectangle(bounds) | SpotlightArea::Ellipse(bounds) => {
+                            bounds.x += dx;
+                            bounds.y += dy;
+                        }
+                    }
+                }
+            }
+            ShapeKind::Counter { center, .. } => offset(center),
+        }
+    }
+}
+
+/// 标注文档：一张底图加上按绘制顺序排列的图形，后面的图形画在上层
+///
+/// 只是数据模型，不依赖任何 UI
+#[derive(Debug, Clone)]
+pub struct AnnotationDocument {
+    base: Image,
+    shapes: Vec<Shape>,
+    next_id: u64,
+}
+
+impl AnnotationDocument {
+    pub fn new(base: Image) -> Self {
+        AnnotationDocument {
+            base,
+            shapes: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    pub fn base(&self) -> &Image {
+        &self.base
+    }
+
+    /// 替换底图，例如裁剪、打码之后
+    pub fn set_base(&mut self, base: Image) -> Image {
+        std::mem::replace(&mut self.base, base)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.base.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.base.height()
+    }
+
+    /// 按绘制顺序排列的图形
+    pub fn shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+
+    /// 分配一个新的图形 id
+    pub fn allocate_id(&mut self) -> ShapeId {
+        let id = ShapeId(self.next_id);
+        self.next_id += 1;
+        id
+    }
+
+    /// 添加到最上层，返回新图形的 id
+    pub fn add(&mut self, kind: ShapeKind, style: Style) -> ShapeId {
+        let id = self.allocate_id();
+        self.shapes.push(Shape {
+            id,
+            kind,
+            style,
+            rotation: 0.0,
+        });
+        self.renumber_counters();
+        id
+    }
+
+    /// 在 index 处插入已有的图形，撤销删除时使用
+    pub fn insert(&mut self, index: usize, shape: Shape) {
+        self.next_id = self.next_id.max(shape.id.0 + 1);
+        let index = index.min(self.shapes.len());
+        self.shapes.insert(index, shape);
+        self.renumber_counters();
+    }
+
+    pub fn index_of(&self, id: ShapeId) -> Option<usize> {
+        self.shapes.iter().position(|s| s.id == id)
+    }
+
+    pub fn get(&self, id: ShapeId) -> Option<&Shape> {
+        self.shapes.iter().find(|s| s.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: ShapeId) -> Option<&mut Shape> {
+        self.shapes.iter_mut().find(|s| s.id == id)
+    }
+
+    /// 删除图形，返回它原来的位置和内容
+    pub fn remove(&mut self, id: ShapeId) -> Option<(usize, Shape)> {
+        let index = self.index_of(id)?;
+        let shape = self.shapes.remove(index);
+        self.renumber_counters();
+        Some((index, shape))
+    }
+
+    /// 按 id（即创建顺序）给步骤徽标从 1 开始编号，与图层顺序无关
+    ///
+    /// 增删图形时自动调用，撤销删除后原来的徽标会回到原来的序号
+    pub fn renumber_counters(&mut self) {
+        let mut counters: Vec<(ShapeId, &mut u32)> = self
+            .shapes
+            .iter_mut()
+            .filter_map(|shape| match &mut shape.kind {
+                ShapeKind::Counter { number, .. } => Some((shape.id, number)),
+                _ => None,
+            })
+            .collect();
+        counters.sort_by_key(|(id, _)| *id);
+
+        for (i, (_, number)) in counters.into_iter().enumerate() {
+            *number = i as u32 + 1;
+        }
+    }
+
+    /// 调整图层顺序，index 越大越靠上
+    pub fn move_to(&mut self, id: ShapeId, index: usize) -> bool {
+        match self.remove(id) {
+            Some((_, shape)) => {
+                let index = index.min(self.shapes.len());
+                self.shapes.insert(index, shape);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn bring_to_front(&mut self, id: ShapeId) -> bool {
+        self.move_to(id, usize::MAX)
+    }
+
+    pub fn send_to_back(&mut self, id: ShapeId) -> bool {
+        self.move_to(id, 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+    }
+
+    /// 平移所有图形，裁剪底图后使用
+    pub fn translate_all(&mut self, dx: f32, dy: f32) {
+        for shape in &mut self.shapes {
+            shape.kind.translate(dx, dy);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> AnnotationDocument {
+        AnnotationDocument::new(Image::new(4, 4, vec![255; 64]))
+    }
+
+    #[test]
+    fn add_remove_and_reorder() {
+        let mut document = document();
+        let line = ShapeKind::Line {
+            from: Point::new(0.0, 0.0),
+            to: Point::new(4.0, 4.0),
+        };
+        let a = document.add(line.clone(), Style::default());
+        let b = document.add(line.clone(), Style::default());
+        let c = document.add(line, Style::default());
+
+        assert!(document.send_to_back(c));
+        let order: Vec<ShapeId> = document.shapes().iter().map(|s| s.id).collect();
+        assert_eq!(order, vec![c, a, b]);
+
+        let (index, shape) = document.remove(a).unwrap();
+        assert_eq!(index, 1);
+        assert!(document.get(a).is_none());
+
+        document.insert(index, shape);
+        assert_eq!(document.index_of(a), Some(1));
+    }
+
+    #[test]
+    fn ids_are_not_reused() {
+        let mut document = document();
+        let bounds = Bounds::new(0.0, 0.0, 1.0, 1.0);
+        let a = document.add(ShapeKind::Rectangle { bounds }, Style::default());
+        document.remove(a);
+        let b = document.add(ShapeKind::Ellipse { bounds }, Style::default());
+
+        assert_ne!(a, b);
+        assert_eq!(
+            Bounds::new(4.0, 4.0, -2.0, -3.0).normalized(),
+            Bounds::new(2.0, 1.0, 2.0, 3.0)
+        );
+    }
+
+    #[test]
+    fn counters_renumber_after_delete() {
+        let mut document = document();
+        let counter = ShapeKind::Counter {
+            center: Point::new(2.0, 2.0),
+            number: 0,
+            radius: 2.0,
+        };
+        let ids: Vec<ShapeId> = (0..3)
+            .map(|_| document.add(counter.clone(), Style::default()))
+            .collect();
+        document.send_to_back(ids[2]);
+
+        let numbers = |document: &AnnotationDocument| -> Vec<u32> {
+            ids.iter()
+                .filter_map(|id| match document.get(*id)?.kind {
+                    ShapeKind::Counter { number, .. } => Some(number),
+                    _ => None,
+                })
+                .collect()
+        };
+        assert_eq!(numbers(&document), vec![1, 2, 3]);
+
+        let (index, shape) = document.remove(ids[1]).unwrap();
+        assert_eq!(numbers(&document), vec![1, 2]);
+
+        document.insert(index, shape);
+        assert_eq!(numbers(&document), vec![1, 2, 3]);
+    }
+}
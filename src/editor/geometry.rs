@@ -0,0 +1,461 @@
+use std::f32::consts::PI;
+
+use crate::editor::annotation::{Bounds, Point};
+
+/// 一个图形展开后的多边形，holes 从 fills 中挖掉
+#[derive(Debug, Clone, Default)]
+pub struct Outline {
+    pub fills: Vec<Vec<Point>>,
+    pub holes: Vec<Vec<Point>>,
+}
+
+impl Outline {
+    pub fn is_empty(&self) -> bool {
+        self.fills.is_empty()
+    }
+
+    /// 像素包围盒 (left, top, right, bottom)，right、bottom 不含
+    pub fn bounds(&self) -> Option<(i32, i32, i32, i32)> {
+        let points = self.fills.iter().flatten();
+        let (mut left, mut top) = (f32::MAX, f32::MAX);
+        let (mut right, mut bottom) = (f32::MIN, f32::MIN);
+        for p in points {
+            left = left.min(p.x);
+            top = top.min(p.y);
+            right = right.max(p.x);
+            bottom = bottom.max(p.y);
+        }
+
+        if left > right {
+            return None;
+        }
+        Some((
+            left.floor() as i32,
+            top.floor() as i32,
+            right.ceil() as i32 + 1,
+            bottom.ceil() as i32 + 1,
+        ))
+    }
+}
+
+/// 按半径决定分段数，保证弦高误差不超过 0.1 像素
+pub fn segments(radius: f32) -> usize {
+    if radius <= 0.1 {
+        return 8;
+    }
+    let step = libm::acosf((1.0 - 0.1 / radius).clamp(-1.0, 1.0));
+    ((2.0 * PI / step).ceil() as usize).clamp(8, 720)
+}
+
+pub fn ellipse(center: Point, rx: f32, ry: f32) -> Vec<Point> {
+    let n = segments(rx.max(ry));
+    (0..n)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f32 / n as f32;
+            Point::new(
+                center.x + rx * libm::cosf(angle),
+                center.y + ry * libm::sinf(angle),
+            )
+        })
+        .collect()
+}
+
+pub fn rectangle(bounds: Bounds) -> Vec<Point> {
+    let b = bounds.normalized();
+    vec![
+        Point::new(b.x, b.y),
+        Point::new(b.x + b.width, b.y),
+        Point::new(b.x + b.width, b.y + b.height),
+        Point::new(b.x, b.y + b.height),
+    ]
+}
+
+/// 圆角矩形，radius 超过短边一半时取短边一半
+pub fn rounded_rect(bounds: Bounds, radius: f32) -> Vec<Point> {
+    bubble(bounds.normalized(), radius, None)
+}
+
+/// 气泡对话框轮廓：圆角矩形在离 target 最近的一边伸出一个尖角指向 target，
+/// target 在矩形内时没有尖角
+pub fn callout(bounds: Bounds, target: Point, radius: f32) -> Vec<Point> {
+    let b = bounds.normalized();
+    let r = radius.min(b.width / 2.0).min(b.height / 2.0).max(0.0);
+    let (right, bottom) = (b.x + b.width, b.y + b.height);
+    if target.x >= b.x && target.x <= right && target.y >= b.y && target.y <= bottom {
+        return bubble(b, r, None);
+    }
+
+    // 按半宽、半高归一化后比较，决定尖角在哪条边上
+    let center = b.center();
+    let dx = (target.x - center.x) / (b.width / 2.0).max(1.0);
+    let dy = (target.y - center.y) / (b.height / 2.0).max(1.0);
+    let (side, min, max) = if dx.abs() > dy.abs() {
+        (if dx > 0.0 { 3 } else { 1 }, b.y, bottom)
+    } else {
+        (if dy > 0.0 { 0 } else { 2 }, b.x, right)
+    };
+
+    // 尖角底边的一半，不能占用圆角
+    let half = ((b.width.min(b.height) * 0.2).clamp(4.0, 12.0)).min((max - min) / 2.0 - r);
+    if half <= 0.0 {
+        return bubble(b, r, None);
+    }
+    let along = if side % 2 == 0 { target.x } else { target.y };
+    let middle = along.clamp(min + r + half, max - r - half);
+
+    // 每条边的走向与轮廓方向一致：下边向左、左边向上、上边向右、右边向下
+    let tail = match side {
+        0 => [
+            Point::new(middle + half, bottom),
+            target,
+            Point::new(middle - half, bottom),
+        ],
+        1 => [
+            Point::new(b.x, middle + half),
+            target,
+            Point::new(b.x, middle - half),
+        ],
+        2 => [
+            Point::new(middle - half, b.y),
+            target,
+            Point::new(middle + half, b.y),
+        ],
+        _ => [
+            Point::new(right, middle - half),
+            target,
+            Point::new(right, middle + half),
+        ],
+    };
+    bubble(b, r, Some((side, tail)))
+}
+
+/// 圆角矩形轮廓，tail 为插入在第 side 条边上的三个点
+fn bubble(b: Bounds, radius: f32, tail: Option<(usize, [Point; 3])>) -> Vec<Point> {
+    let r = radius.min(b.width / 2.0).min(b.height / 2.0).max(0.0);
+    if r == 0.0 && tail.is_none() {
+        return rectangle(b);
+    }
+
+    // 每个角一段四分之一圆弧，依次为右下、左下、左上、右上，第 k 个角之后是第 k 条边
+    let n = segments(r) / 4;
+    let corners = [
+        (Point::new(b.x + b.width - r, b.y + b.height - r), 0.0),
+        (Point::new(b.x + r, b.y + b.height - r), PI / 2.0),
+        (Point::new(b.x + r, b.y + r), PI),
+        (Point::new(b.x + b.width - r, b.y + r), PI * 1.5),
+    ];
+
+    let mut points = Vec::new();
+    for (k, (center, start)) in corners.iter().enumerate() {
+        if r == 0.0 {
+            points.push(*center);
+        } else {
+            points.extend((0..=n).map(|i| {
+                let angle = start + PI / 2.0 * i as f32 / n as f32;
+                Point::new(
+                    center.x + r * libm::cosf(angle),
+                    center.y + r * libm::sinf(angle),
+                )
+            }));
+        }
+
+        if let Some((side, tail)) = tail {
+            if side == k {
+                points.extend(tail);
+            }
+        }
+    }
+    points
+}
+
+/// 折线描边：每段一个四边形，拐点和端点各一个圆，合并后得到圆角圆头的线
+pub fn polyline(points: &[Point], width: f32) -> Outline {
+    let half = width / 2.0;
+    let mut outline = Outline::default();
+
+    for p in points {
+        outline.fills.push(ellipse(*p, half, half));
+    }
+
+    for pair in points.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0.0 {
+            continue;
+        }
+
+        let (nx, ny) = (-dy / length * half, dx / length * half);
+        outline.fills.push(vec![
+            Point::new(a.x + nx, a.y + ny),
+            Point::new(b.x + nx, b.y + ny),
+            Point::new(b.x - nx, b.y - ny),
+            Point::new(a.x - nx, a.y - ny),
+        ]);
+    }
+
+    outline
+}
+
+/// 二次贝塞尔曲线展开成折线
+pub fn quadratic(from: Point, control: Point, to: Point) -> Vec<Point> {
+    let length = distance(from, control) + distance(control, to);
+    let n = ((length / 4.0).ceil() as usize).clamp(8, 256);
+    (0..=n)
+        .map(|i| {
+            let t = i as f32 / n as f32;
+            let (a, b, c) = ((1.0 - t) * (1.0 - t), 2.0 * t * (1.0 - t), t * t);
+            Point::new(
+                a * from.x + b * control.x + c * to.x,
+                a * from.y + b * control.y + c * to.y,
+            )
+        })
+        .collect()
+}
+
+pub fn distance(a: Point, b: Point) -> f32 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+}
+
+fn lerp(a: Point, b: Point, t: f32) -> Point {
+    Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t)
+}
+
+fn length(points: &[Point]) -> f32 {
+    points.windows(2).map(|w| distance(w[0], w[1])).sum()
+}
+
+/// 截取折线上弧长在 [start, end] 之间的部分
+fn trim(points: &[Point], start: f32, end: f32) -> Vec<Point> {
+    let mut result = Vec::new();
+    let mut travelled = 0.0;
+
+    for pair in points.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let segment = distance(a, b);
+        let next = travelled + segment;
+        if segment > 0.0 && next >= start && travelled <= end {
+            if result.is_empty() {
+                result.push(lerp(a, b, ((start - travelled) / segment).max(0.0)));
+            }
+            if next > end {
+                result.push(lerp(a, b, (end - travelled) / segment));
+                break;
+            }
+            result.push(b);
+        }
+        travelled = next;
+    }
+    result
+}
+
+/// 三角形箭头，尖端在 tip，底边中点在 base
+fn head(tip: Point, base: Point, size: f32) -> Vec<Point> {
+    let length = distance(base, tip).max(f32::EPSILON);
+    let (nx, ny) = (
+        -(tip.y - base.y) / length * size / 2.0,
+        (tip.x - base.x) / length * size / 2.0,
+    );
+    vec![
+        tip,
+        Point::new(base.x + nx, base.y + ny),
+        Point::new(base.x - nx, base.y - ny),
+    ]
+}
+
+/// 渐变宽度的折线，width 的参数是弧长占比 0.0 ~ 1.0
+fn taper(points: &[Point], width: impl Fn(f32) -> f32) -> Vec<Point> {
+    let total = length(points).max(f32::EPSILON);
+    let mut left = Vec::with_capacity(points.len());
+    let mut right = Vec::with_capacity(points.len());
+    let mut travelled = 0.0;
+
+    for (i, p) in points.iter().enumerate() {
+        if i > 0 {
+            travelled += distance(points[i - 1], *p);
+        }
+
+        // 法线取前后两点连线的垂直方向
+        let a = points[i.saturating_sub(1)];
+        let b = points[(i + 1).min(points.len() - 1)];
+        let d = distance(a, b).max(f32::EPSILON);
+        let half = width(travelled / total) / 2.0;
+        let (nx, ny) = (-(b.y - a.y) / d * half, (b.x - a.x) / d * half);
+        left.push(Point::new(p.x + nx, p.y + ny));
+        right.push(Point::new(p.x - nx, p.y - ny));
+    }
+
+    left.extend(right.into_iter().rev());
+    left
+}
+
+/// 箭头：箭身在箭头底部截止，避免线头从尖端露出
+///
+/// control 为二次贝塞尔控制点，double 时两端都有箭头，tapered 时箭身从尾部的 1/4 线宽
+/// 逐渐变粗（双头箭头不收窄）
+pub fn arrow(
+    from: Point,
+    to: Point,
+    control: Option<Point>,
+    width: f32,
+    double: bool,
+    tapered: bool,
+) -> Outline {
+    let path = match control {
+        Some(control) => quadratic(from, control, to),
+        None => vec![from, to],
+    };
+    let total = length(&path);
+    if total == 0.0 {
+        return polyline(&[from], width);
+    }
+
+    let heads = if double { 2.0 } else { 1.0 };
+    let size = (width * 4.0).max(12.0).min(total / heads);
+    let start = if double { size } else { 0.0 };
+    let shaft = trim(&path, start, total - size);
+
+    let mut outline = if tapered && !double {
+        Outline {
+            fills: vec![taper(&shaft, |t| width * (0.25 + 0.75 * t))],
+            holes: Vec::new(),
+        }
+    } else {
+        polyline(&shaft, width)
+    };
+
+    // 箭头方向取尖端到箭身末端的连线，曲线箭头也能对准曲线走向
+    let end = trim(&path, total - size, total);
+    outline.fills.push(head(to, end[0], size));
+    if double {
+        let begin = trim(&path, 0.0, size);
+        outline.fills.push(head(from, begin[begin.len() - 1], size));
+    }
+    outline
+}
+
+/// 矩形、椭圆的描边：外轮廓减去内轮廓
+pub fn ring(outer: Vec<Point>, inner: Option<Vec<Point>>) -> Outline {
+    Outline {
+        fills: vec![outer],
+        holes: inner.into_iter().collect(),
+    }
+}
+
+/// 绕 pivot 顺时针旋转（y 轴向下）
+pub fn rotate_point(p: Point, pivot: Point, degrees: f32) -> Point {
+    let (sin, cos) = libm::sincosf(degrees.to_radians());
+    let (dx, dy) = (p.x - pivot.x, p.y - pivot.y);
+    Point::new(pivot.x + dx * cos - dy * sin, pivot.y + dx * sin + dy * cos)
+}
+
+impl Outline {
+    pub fn rotate(&self, pivot: Point, degrees: f32) -> Outline {
+        let rotate = |contours: &Vec<Vec<Point>>| -> Vec<Vec<Point>> {
+            contours
+                .iter()
+                .map(|c| c.iter().map(|p| rotate_point(*p, pivot, degrees)).collect())
+                .collect()
+        };
+        Outline {
+            fills: rotate(&self.fills),
+            holes: rotate(&self.holes),
+        }
+    }
+
+    /// 点是否在轮廓内（非零规则，减去 holes）
+    pub fn contains(&self, p: Point) -> bool {
+        self.fills.iter().any(|c| winding(c, p) != 0)
+            && !self.holes.iter().any(|c| winding(c, p) != 0)
+    }
+
+    /// 点到所有轮廓边的最短距离
+    pub fn distance_to_edges(&self, p: Point) -> f32 {
+        self.fills
+            .iter()
+            .chain(&self.holes)
+            .map(|c| distance_to_contour(c, p))
+            .fold(f32::MAX, f32::min)
+    }
+}
+
+/// 闭合多边形绕点 p 的圈数
+pub fn winding(points: &[Point], p: Point) -> i32 {
+    let mut winding = 0;
+    for i in 0..points.len() {
+        let (a, b) = (points[i], points[(i + 1) % points.len()]);
+        let cross = (b.x - a.x) * (p.y - a.y) - (p.x - a.x) * (b.y - a.y);
+        if a.y <= p.y && b.y > p.y && cross > 0.0 {
+            winding += 1;
+        } else if a.y > p.y && b.y <= p.y && cross < 0.0 {
+            winding -= 1;
+        }
+    }
+    winding
+}
+
+/// 点到线段的距离
+pub fn distance_to_segment(p: Point, a: Point, b: Point) -> f32 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length = dx * dx + dy * dy;
+    if length == 0.0 {
+        return distance(p, a);
+    }
+    let t = (((p.x - a.x) * dx + (p.y - a.y) * dy) / length).clamp(0.0, 1.0);
+    distance(p, lerp(a, b, t))
+}
+
+/// 点到折线的距离，只有一个点时为到该点的距离
+pub fn distance_to_polyline(points: &[Point], p: Point) -> f32 {
+    match points {
+        [] => f32::MAX,
+        [single] => distance(*single, p),
+        _ => points
+            .windows(2)
+            .map(|w| distance_to_segment(p, w[0], w[1]))
+            .fold(f32::MAX, f32::min),
+    }
+}
+
+fn distance_to_contour(points: &[Point], p: Point) -> f32 {
+    (0..points.len())
+        .map(|i| distance_to_segment(p, points[i], points[(i + 1) % points.len()]))
+        .fold(f32::MAX, f32::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::raster::signed_area;
+
+    #[test]
+    fn callout_tail_points_at_target() {
+        let bounds = Bounds::new(10.0, 10.0, 100.0, 40.0);
+        let target = Point::new(60.0, 90.0);
+        let contour = callout(bounds, target, 6.0);
+
+        assert!(contour.contains(&target));
+        // 尖角在下边，轮廓面积比圆角矩形大
+        let plain = rounded_rect(bounds, 6.0);
+        assert!(signed_area(&contour).abs() > signed_area(&plain).abs());
+        assert!(!callout(bounds, Point::new(50.0, 30.0), 6.0).contains(&Point::new(50.0, 30.0)));
+    }
+
+    #[test]
+    fn curved_double_arrow_has_two_heads() {
+        let from = Point::new(0.0, 0.0);
+        let to = Point::new(100.0, 0.0);
+        let outline = arrow(from, to, Some(Point::new(50.0, 50.0)), 4.0, true, false);
+
+        let tips: Vec<Point> = outline
+            .fills
+            .iter()
+            .filter(|f| f.len() == 3)
+            .map(|f| f[0])
+            .collect();
+        assert_eq!(tips, vec![to, from]);
+        // 曲线向下弯，终点处箭头朝右上方
+        let end = &outline.fills[outline.fills.len() - 2];
+        assert!(end[1].y > 0.0 && end[2].y > 0.0);
+    }
+}
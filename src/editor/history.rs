@@ -0,0 +1,468 @@
+use crate::core::image::{Image, Rect, Redaction, Region};
+use crate::editor::annotation::{AnnotationDocument, Shape, ShapeId, ShapeKind, Style};
+
+/// 默认最多保留 256MB 的图像历史
+pub const DEFAULT_IMAGE_MEMORY_LIMIT: usize = 256 * 1024 * 1024;
+
+/// 可撤销的编辑操作
+#[derive(Debug, Clone)]
+pub enum Command {
+    AddShape {
+        index: usize,
+        shape: Shape,
+    },
+    DeleteShape {
+        index: usize,
+        shape: Shape,
+    },
+    /// 平移图形，连续拖动会合并成一条
+    MoveShape {
+        id: ShapeId,
+        dx: f32,
+        dy: f32,
+    },
+    /// 修改图形的样式或几何形状
+    UpdateShape {
+        before: Shape,
+        after: Shape,
+    },
+    /// 替换底图，所有图形同时平移 (dx, dy)，用于裁剪、打码
+    ReplaceBase {
+        before: Image,
+        after: Image,
+        dx: f32,
+        dy: f32,
+    },
+}
+
+impl Command {
+    /// 在最上层添加图形
+    pub fn add(document: &mut AnnotationDocument, kind: ShapeKind, style: Style) -> Command {
+        Command::AddShape {
+            index: document.shapes().len(),
+            shape: Shape {
+                id: document.allocate_id(),
+                kind,
+                style,
+                rotation: 0.0,
+            },
+        }
+    }
+
+    pub fn delete(document: &AnnotationDocument, id: ShapeId) -> Option<Command> {
+        let index = document.index_of(id)?;
+        Some(Command::DeleteShape {
+            index,
+            shape: document.shapes()[index].clone(),
+        })
+    }
+
+    /// 裁剪底图，图形跟随移动，rect 与底图不相交时返回 None
+    pub fn crop(document: &AnnotationDocument, rect: Rect) -> Option<Command> {
+        let before = document.base();
+        let clip = rect.intersect(&before.bounds())?;
+        let after = before.crop(clip)?;
+
+        Some(Command::ReplaceBase {
+            before: before.clone(),
+            after,
+            dx: -clip.x as f32,
+            dy: -clip.y as f32,
+        })
+    }
+
+    /// 对底图打码
+    pub fn redact(document: &AnnotationDocument, region: &Region, redaction: Redaction) -> Command {
+        let before = document.base().clone();
+        let mut after = before.clone();
+        after.redact(region, redaction);
+
+        Command::ReplaceBase {
+            before,
+            after,
+            dx: 0.0,
+            dy: 0.0,
+        }
+    }
+
+    fn apply(&self, document: &mut AnnotationDocument) {
+        match self {
+            Command::AddShape { index, shape } => document.insert(*index, shape.clone()),
+            Command::DeleteShape { shape, .. } => {
+                document.remove(shape.id);
+            }
+            Command::MoveShape { id, dx, dy } => {
+                if let Some(shape) = document.get_mut(*id) {
+                    shape.kind.translate(*dx, *dy);
+                }
+            }
+            Command::UpdateShape { after, .. } => {
+                if let Some(shape) = document.get_mut(after.id) {
+                    *shape = after.clone();
+                }
+                document.renumber_counters();
+            }
+            Command::ReplaceBase { after, dx, dy, .. } => {
+                document.set_base(after.clone());
+                document.translate_all(*dx, *dy);
+            }
+        }
+    }
+
+    fn revert(&self, document: &mut AnnotationDocument) {
+        match self {
+            Command::AddShape { shape, .. } => {
+                document.remove(shape.id);
+            }
+            Command::DeleteShape { index, shape } => document.insert(*index, shape.clone()),
+            Command::MoveShape { id, dx, dy } => {
+                if let Some(shape) = document.get_mut(*id) {
+                    shape.kind.translate(-dx, -dy);
+                }
+            }
+            Command::UpdateShape { before, .. } => {
+                if let Some(shape) = document.get_mut(before.id) {
+                    *shape = before.clone();
+                }
+                document.renumber_counters();
+            }
+            Command::ReplaceBase { before, dx, dy, .. } => {
+                document.set_base(before.clone());
+                document.translate_all(-dx, -dy);
+            }
+        }
+    }
+
+    /// 命令持有的图像字节数
+    fn image_bytes(&self) -> usize {
+        match self {
+            Command::ReplaceBase { before, after, .. } => before.rgba().len() + after.rgba().len(),
+            _ => 0,
+        }
+    }
+
+    /// 尝试把 next 合并到自己身上
+    fn merge(&mut self, next: &Command) -> bool {
+        match (self, next) {
+            (
+                Command::MoveShape { id, dx, dy },
+                Command::MoveShape {
+                    id: next_id,
+                    dx: next_dx,
+                    dy: next_dy,
+                },
+            ) if id == next_id => {
+                *dx += next_dx;
+                *dy += next_dy;
+                true
+            }
+            (
+                Command::UpdateShape { after, .. },
+                Command::UpdateShape {
+                    before,
+                    after: next,
+                },
+            ) if after.id == next.id && after == before => {
+                *after = next.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    command: Command,
+    /// 执行后文档状态的编号，用于判断是否回到了保存点
+    revision: u64,
+}
+
+/// 撤销、重做历史
+#[derive(Debug, Clone)]
+pub struct History {
+    undo: Vec<Entry>,
+    redo: Vec<Entry>,
+    next_revision: u64,
+    /// 撤销栈为空时文档状态的编号，最早的记录被丢弃或历史被清空后不再是 0
+    base_revision: u64,
+    saved_revision: u64,
+    /// 为 true 时连续的同类命令会合并，例如拖动过程中的多次移动
+    merging: bool,
+    image_memory_limit: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History::new(DEFAULT_IMAGE_MEMORY_LIMIT)
+    }
+}
+
+impl History {
+    /// image_memory_limit 为撤销、重做栈中图像数据的总字节数上限
+    pub fn new(image_memory_limit: usize) -> Self {
+        History {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            next_revision: 1,
+            base_revision: 0,
+            saved_revision: 0,
+            merging: false,
+            image_memory_limit,
+        }
+    }
+
+    fn revision(&self) -> u64 {
+        self.undo
+            .last()
+            .map(|e| e.revision)
+            .unwrap_or(self.base_revision)
+    }
+
+    fn allocate_revision(&mut self) -> u64 {
+        let revision = self.next_revision;
+        self.next_revision += 1;
+        revision
+    }
+
+    /// 执行命令并记录到历史，清空重做栈
+    pub fn execute(&mut self, document: &mut AnnotationDocument, command: Command) {
+        command.apply(document);
+        self.redo.clear();
+
+        if self.merging {
+            if let Some(last) = self.undo.last_mut() {
+                if last.command.merge(&command) {
+                    // 合并后状态变了，需要新的编号，否则会误判为未修改
+                    last.revision = self.next_revision;
+                    self.next_revision += 1;
+                    return;
+                }
+            }
+        }
+
+        let revision = self.allocate_revision();
+        self.undo.push(Entry { command, revision });
+        self.enforce_memory_limit();
+    }
+
+    /// 开始一次连续操作（例如按下鼠标开始拖动），之后的同类命令会合并
+    pub fn begin_merge(&mut self) {
+        self.merging = true;
+    }
+
+    /// 结束连续操作（例如松开鼠标）
+    pub fn end_merge(&mut self) {
+        self.merging = false;
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo(&mut self, document: &mut AnnotationDocument) -> bool {
+        self.merging = false;
+        match self.undo.pop() {
+            Some(entry) => {
+                entry.command.revert(document);
+                self.redo.push(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self, document: &mut AnnotationDocument) -> bool {
+        self.merging = false;
+        match self.redo.pop() {
+            Some(entry) => {
+                entry.command.apply(document);
+                self.undo.push(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 标记当前状态为已保存
+    pub fn mark_saved(&mut self) {
+        self.merging = false;
+        self.saved_revision = self.revision();
+    }
+
+    /// 与上次保存时相比是否有修改
+    pub fn is_dirty(&self) -> bool {
+        self.revision() != self.saved_revision
+    }
+
+    /// 清空历史，文档本身不变，当前状态的编号不变，所以是否已修改的状态也不变
+    pub fn clear(&mut self) {
+        self.base_revision = self.revision();
+        self.undo.clear();
+        self.redo.clear();
+        self.merging = false;
+    }
+
+    /// 撤销、重做栈中图像数据的总字节数
+    pub fn image_memory(&self) -> usize {
+        self.undo
+            .iter()
+            .chain(&self.redo)
+            .map(|e| e.command.image_bytes())
+            .sum()
+    }
+
+    /// 超出上限时从最早的撤销记录开始丢弃，最新的一条总是保留，保证刚执行的命令可以撤销
+    ///
+    /// 只在 execute 之后调用，此时重做栈已经清空
+    fn enforce_memory_limit(&mut self) {
+        let mut memory = self.image_memory();
+        while memory > self.image_memory_limit && self.undo.len() > 1 {
+            let entry = self.undo.remove(0);
+            memory -= entry.command.image_bytes();
+            // 丢弃的记录执行后的状态成为撤销栈最底部的状态
+            self.base_revision = entry.revision;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::editor::annotation::Point;
+
+    fn document() -> AnnotationDocument {
+        AnnotationDocument::new(Image::new(10, 10, [255, 255, 255, 255].repeat(100)))
+    }
+
+    fn line() -> ShapeKind {
+        ShapeKind::Line {
+            from: Point::new(0.0, 0.0),
+            to: Point::new(5.0, 5.0),
+        }
+    }
+
+    #[test]
+    fn undo_redo_add_and_delete() {
+        let mut document = document();
+        let mut history = History::default();
+
+        let add = Command::add(&mut document, line(), Style::default());
+        history.execute(&mut document, add);
+        let id = document.shapes()[0].id;
+        let delete = Command::delete(&document, id).unwrap();
+        history.execute(&mut document, delete);
+        assert!(document.shapes().is_empty());
+
+        assert!(history.undo(&mut document));
+        assert_eq!(document.shapes()[0].id, id);
+        assert!(history.undo(&mut document));
+        assert!(document.shapes().is_empty());
+        assert!(!history.undo(&mut document));
+
+        assert!(history.redo(&mut document));
+        assert_eq!(document.shapes().len(), 1);
+    }
+
+    #[test]
+    fn drags_merge_into_one_step() {
+        let mut document = document();
+        let id = document.add(line(), Style::default());
+        let mut history = History::default();
+
+        history.begin_merge();
+        for _ in 0..5 {
+            history.execute(
+                &mut document,
+                Command::MoveShape {
+                    id,
+                    dx: 1.0,
+                    dy: 2.0,
+                },
+            );
+        }
+        history.end_merge();
+        history.execute(
+            &mut document,
+            Command::MoveShape {
+                id,
+                dx: 1.0,
+                dy: 0.0,
+            },
+        );
+
+        history.undo(&mut document);
+        history.undo(&mut document);
+        assert!(!history.can_undo());
+        assert_eq!(document.get(id).unwrap().kind, line());
+    }
+
+    #[test]
+    fn save_point_and_crop() {
+        let mut document = document();
+        let id = document.add(line(), Style::default());
+        let mut history = History::default();
+        assert!(!history.is_dirty());
+
+        let crop = Command::crop(&document, Rect::new(2, 3, 5, 5)).unwrap();
+        history.execute(&mut document, crop);
+        assert_eq!(document.width(), 5);
+        assert_eq!(
+            document.get(id).unwrap().kind,
+            ShapeKind::Line {
+                from: Point::new(-2.0, -3.0),
+                to: Point::new(3.0, 2.0),
+            }
+        );
+        assert!(history.is_dirty());
+
+        history.mark_saved();
+        history.undo(&mut document);
+        assert!(history.is_dirty());
+        assert_eq!(document.width(), 10);
+        history.redo(&mut document);
+        assert!(!history.is_dirty());
+    }
+
+    #[test]
+    fn image_memory_limit_drops_oldest() {
+        let mut document = document();
+        // 每次裁剪持有 before + after，10x10 与 9x9 约 724 字节
+        let mut history = History::new(1000);
+
+        let first = Command::crop(&document, Rect::new(0, 0, 9, 9)).unwrap();
+        history.execute(&mut document, first);
+        let second = Command::crop(&document, Rect::new(0, 0, 8, 8)).unwrap();
+        history.execute(&mut document, second);
+
+        assert!(history.image_memory() <= 1000);
+        assert!(history.undo(&mut document));
+        assert!(!history.can_undo());
+        assert_eq!(document.width(), 9);
+        // 最早的记录被丢弃后回到的不是初始状态，仍然是已修改
+        assert!(history.is_dirty());
+    }
+
+    #[test]
+    fn oversized_command_can_still_be_undone() {
+        let mut document = document();
+        let mut history = History::new(100);
+
+        let crop = Command::crop(&document, Rect::new(0, 0, 9, 9)).unwrap();
+        history.execute(&mut document, crop);
+        history.mark_saved();
+        history.clear();
+        assert!(!history.is_dirty());
+
+        let crop = Command::crop(&document, Rect::new(0, 0, 8, 8)).unwrap();
+        history.execute(&mut document, crop);
+        assert!(history.can_undo());
+        assert!(history.undo(&mut document));
+        assert_eq!(document.width(), 9);
+        assert!(!history.is_dirty());
+    }
+}
@@ -0,0 +1,11 @@
+pub mod annotation;
+pub mod geometry;
+pub mod history;
+pub mod project;
+pub mod raster;
+pub mod render;
+pub mod selection;
+pub mod spotlight;
+pub mod svg;
+pub mod text;
+pub mod transform;
@@ -0,0 +1,260 @@
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::core::image::Image;
+use crate::editor::annotation::{AnnotationDocument, Shape};
+
+/// 工程文件扩展名
+pub const PROJECT_EXTENSION: &str = "snap";
+
+/// 当前格式版本
+///
+/// 只增加可选字段时不需要改版本；字段改名、结构调整时版本加一，
+/// 并在 MIGRATIONS 末尾追加把上一版 annotations.json 升级到新版的函数
+pub const PROJECT_VERSION: u32 = 1;
+
+/// MIGRATIONS[i] 把版本 i + 1 的 annotations.json 升级到版本 i + 2
+const MIGRATIONS: &[fn(&mut Value) -> Result<()>] = &[];
+const _: () = assert!(MIGRATIONS.len() == PROJECT_VERSION as usize - 1);
+
+const FORMAT: &str = "screensnap";
+const MANIFEST: &str = "manifest.json";
+const BASE: &str = "base.png";
+const ANNOTATIONS: &str = "annotations.json";
+
+/// 单个条目解压后的上限，zip 头里的大小不可信，不能拿来预分配
+const MAX_ENTRY_SIZE: u64 = 256 * 1024 * 1024;
+
+/// 工程元数据
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    pub title: Option<String>,
+    /// 创建和最后保存时间，Unix 时间戳（秒）
+    pub created: u64,
+    pub modified: u64,
+    /// 保存文件的程序和版本
+    pub generator: String,
+}
+
+/// manifest.json
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format: String,
+    version: u32,
+    #[serde(default)]
+    metadata: Metadata,
+}
+
+/// annotations.json
+#[derive(Debug, Serialize, Deserialize)]
+struct Annotations {
+    shapes: Vec<Shape>,
+}
+
+/// `.snap` 工程文件：zip 容器，包含原始截图、标注和元数据，可以重新打开继续编辑
+///
+/// ```text
+/// manifest.json     格式名、版本号、元数据
+/// base.png          未合成标注的底图
+/// annotations.json  按绘制顺序排列的图形
+/// ```
+pub struct Project {
+    pub document: AnnotationDocument,
+    pub metadata: Metadata,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// 把旧版本的 annotations.json 逐版升级到当前版本
+fn migrate(mut annotations: Value, version: u32) -> Result<Value> {
+    if version == 0 {
+        bail!("Invalid project version 0");
+    }
+    if version > PROJECT_VERSION {
+        bail!(
+            "Project version {version} is newer than supported version {PROJECT_VERSION}, please upgrade"
+        );
+    }
+
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        migration(&mut annotations)?;
+    }
+    Ok(annotations)
+}
+
+fn write_archive(manifest: &Manifest, base: &[u8], annotations: &Value) -> Result<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    // PNG 本身已经压缩过
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+
+    zip.start_file(MANIFEST, deflated)?;
+    zip.write_all(&serde_json::to_vec_pretty(manifest)?)?;
+    zip.start_file(BASE, stored)?;
+    zip.write_all(base)?;
+    zip.start_file(ANNOTATIONS, deflated)?;
+    zip.write_all(&serde_json::to_vec_pretty(annotations)?)?;
+
+    Ok(zip.finish()?.into_inner())
+}
+
+fn read_entry(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<Vec<u8>> {
+    let file = archive
+        .by_name(name)
+        .map_err(|_| anyhow!("Project is missing {name}"))?;
+    let mut data = Vec::new();
+    file.take(MAX_ENTRY_SIZE + 1).read_to_end(&mut data)?;
+    if data.len() as u64 > MAX_ENTRY_SIZE {
+        bail!("Project entry {name} is larger than {MAX_ENTRY_SIZE} bytes");
+    }
+    Ok(data)
+}
+
+impl Project {
+    pub fn new(document: AnnotationDocument) -> Self {
+        let time = now();
+        Project {
+            document,
+            metadata: Metadata {
+                title: None,
+                created: time,
+                modified: time,
+                generator: format!("{} {}", FORMAT, env!("CARGO_PKG_VERSION")),
+            },
+        }
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let manifest = Manifest {
+            format: FORMAT.to_string(),
+            version: PROJECT_VERSION,
+            metadata: self.metadata.clone(),
+        };
+        let annotations = serde_json::to_value(Annotations {
+            shapes: self.document.shapes().to_vec(),
+        })?;
+
+        write_archive(&manifest, &self.document.base().to_png()?, &annotations)
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Project> {
+        let mut archive = ZipArchive::new(Cursor::new(data))?;
+
+        let manifest: Manifest = serde_json::from_slice(&read_entry(&mut archive, MANIFEST)?)?;
+        if manifest.format != FORMAT {
+            bail!("Not a {FORMAT} project: {}", manifest.format);
+        }
+
+        let annotations = serde_json::from_slice(&read_entry(&mut archive, ANNOTATIONS)?)?;
+        let annotations: Annotations =
+            serde_json::from_value(migrate(annotations, manifest.version)?)?;
+        let base = Image::from_png(&read_entry(&mut archive, BASE)?)?;
+
+        let mut document = AnnotationDocument::new(base);
+        for shape in annotations.shapes {
+            document.insert(usize::MAX, shape);
+        }
+
+        Ok(Project {
+            document,
+            metadata: manifest.metadata,
+        })
+    }
+
+    /// 更新修改时间后保存，先写临时文件再改名，保存中途出错不会损坏原文件
+    pub fn save<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        self.metadata.modified = now();
+        let data = self.to_bytes()?;
+
+        let temp = path.with_extension(format!("{PROJECT_EXTENSION}.tmp"));
+        fs::write(&temp, data)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Project> {
+        Project::from_bytes(&fs::read(path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::image::Color;
+    use crate::editor::annotation::{
+        Bounds, Point, ShapeKind, SpotlightArea, SpotlightEffect, Style,
+    };
+
+    fn project() -> Project {
+        let mut document = AnnotationDocument::new(Image::new(4, 4, [10, 20, 30, 255].repeat(16)));
+        document.add(
+            ShapeKind::Arrow {
+                from: Point::new(0.0, 0.0),
+                to: Point::new(3.0, 3.0),
+                control: Some(Point::new(3.0, 0.0)),
+                double: false,
+                tapered: true,
+            },
+            Style {
+                fill: Some(Color::new(1, 2, 3, 4)),
+                ..Style::default()
+            },
+        );
+        document.add(
+            ShapeKind::Spotlight {
+                areas: vec![SpotlightArea::Ellipse(Bounds::new(1.0, 1.0, 2.0, 2.0))],
+                effect: SpotlightEffect::Blur { radius: 4.0 },
+                feather: 2.0,
+            },
+            Style::default(),
+        );
+
+        let mut project = Project::new(document);
+        project.metadata.title = Some("截图".to_string());
+        project
+    }
+
+    #[test]
+    fn round_trip() {
+        let project = project();
+        let loaded = Project::from_bytes(&project.to_bytes().unwrap()).unwrap();
+
+        assert_eq!(loaded.metadata, project.metadata);
+        assert_eq!(loaded.document.shapes(), project.document.shapes());
+        assert_eq!(
+            loaded.document.base().rgba(),
+            project.document.base().rgba()
+        );
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let project = project();
+        let manifest = Manifest {
+            format: FORMAT.to_string(),
+            version: PROJECT_VERSION + 1,
+            metadata: project.metadata.clone(),
+        };
+        let base = project.document.base().to_png().unwrap();
+        let data = write_archive(&manifest, &base, &serde_json::json!({ "shapes": [] })).unwrap();
+
+        let error = Project::from_bytes(&data).err().unwrap();
+        assert!(error.to_string().contains("newer"));
+        assert!(Project::from_bytes(b"not a zip").is_err());
+    }
+}
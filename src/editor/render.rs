@@ -0,0 +1,518 @@
+use crate::core::image::{blend_pixel, AlphaMode, BlendMode, Color, Filter, Image};
+use crate::editor::annotation::{
+    AnnotationDocument, Bounds, Point, Shape, ShapeKind, SpotlightArea, SpotlightEffect, Style,
+};
+use crate::editor::geometry::{
+    arrow, callout, ellipse, polyline, rectangle, ring, rotate_point, Outline,
+};
+use crate::editor::raster::Rasterizer;
+use crate::editor::spotlight::spotlight;
+use crate::editor::text::{Align, FontSet, TextBackground, TextOutline, TextStyle};
+use crate::editor::transform::local_bounds;
+
+/// 荧光笔的不透明度
+pub const HIGHLIGHTER_OPACITY: f32 = 0.5;
+
+/// 气泡对话框的圆角半径和内边距，相对字号
+pub const CALLOUT_PADDING: f32 = 0.5;
+
+/// 文字图形的排版样式，字号、换行宽度、描边宽度按 scale 缩放
+///
+/// SVG 导出使用同样的样式，保证换行位置和位图一致
+pub fn text_style(
+    size: f32,
+    max_width: Option<f32>,
+    outline: Option<Color>,
+    style: &Style,
+    scale: f32,
+) -> TextStyle {
+    let size = size * scale;
+    TextStyle {
+        size,
+        color: style.stroke,
+        max_width: max_width.map(|w| w * scale),
+        line_spacing: 1.0,
+        align: Align::Left,
+        outline: outline.map(|color| TextOutline {
+            color,
+            width: style.width * scale,
+        }),
+        background: style.fill.map(|color| TextBackground {
+            color,
+            padding: size / 4.0,
+            radius: size / 4.0,
+        }),
+    }
+}
+
+/// 气泡对话框内文字的排版样式，size、width 为缩放后的字号和气泡宽度
+pub fn callout_text_style(size: f32, width: f32, style: &Style) -> TextStyle {
+    TextStyle {
+        size,
+        color: style.stroke,
+        max_width: Some((width - size * CALLOUT_PADDING * 2.0).max(size)),
+        ..TextStyle::default()
+    }
+}
+
+/// 软件渲染器，把标注文档合成到一张图上，不需要 GPU
+pub struct Renderer {
+    /// 输出相对底图的缩放比例，例如 HiDPI 导出时为 2.0
+    pub scale: f32,
+    /// 文字和序号徽标使用的字体，默认为内置字体
+    pub fonts: FontSet,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Renderer {
+            scale: 1.0,
+            fonts: FontSet::bundled(),
+        }
+    }
+}
+
+impl Renderer {
+    pub fn new(scale: f32) -> Self {
+        Renderer {
+            scale,
+            ..Renderer::default()
+        }
+    }
+
+    /// 替换字体，例如加入 CJK 后备字体
+    pub fn with_fonts(mut self, fonts: FontSet) -> Self {
+        self.fonts = fonts;
+        self
+    }
+
+    fn point(&self, p: Point) -> Point {
+        Point::new(p.x * self.scale, p.y * self.scale)
+    }
+
+    fn bounds(&self, b: Bounds) -> Bounds {
+        Bounds::new(
+            b.x * self.scale,
+            b.y * self.scale,
+            b.width * self.scale,
+            b.height * self.scale,
+        )
+    }
+
+    /// 把标注合成到（按 scale 缩放后的）底图上
+    pub fn render(&self, document: &AnnotationDocument) -> Image {
+        let base = document.base();
+        let mut image = if self.scale == 1.0 {
+            base.clone()
+        } else {
+            let width = (base.width() as f32 * self.scale).round() as u32;
+            let height = (base.height() as f32 * self.scale).round() as u32;
+            base.resize(width, height, Filter::Bicubic)
+        };
+
+        for shape in document.shapes() {
+            self.render_shape(&mut image, shape);
+        }
+
+        image
+    }
+
+    /// 在 image 上绘制一个图形，坐标按 scale 缩放
+    pub fn render_shape(&self, image: &mut Image, shape: &Shape) {
+        let style = &shape.style;
+        let width = style.width * self.scale;
+
+        // 矩形、椭圆、文字、气泡绕本地包围盒中心旋转，没有旋转时不必排版文字求包围盒
+        let (pivot, rotation) = if shape.kind.is_rotatable() && shape.rotation != 0.0 {
+            let center = local_bounds(shape, &self.fonts).center();
+            (self.point(center), shape.rotation)
+        } else {
+            (Point::default(), 0.0)
+        };
+        let paint = |image: &mut Image, outline: &Outline, color, opacity, mode| {
+            if rotation == 0.0 {
+                self.paint(image, outline, color, opacity, mode);
+            } else {
+                self.paint(
+                    image,
+                    &outline.rotate(pivot, rotation),
+                    color,
+                    opacity,
+                    mode,
+                );
+            }
+        };
+
+        match &shape.kind {
+            ShapeKind::Rectangle { bounds } => {
+                let b = self.bounds(*bounds).normalized();
+                if let Some(fill) = style.fill {
+                    paint(
+                        image,
+                        &ring(rectangle(b), None),
+                        fill,
+                        style.opacity,
+                        BlendMode::Over,
+                    );
+                }
+
+                let half = width / 2.0;
+                let outer = Bounds::new(b.x - half, b.y - half, b.width + width, b.height + width);
+                let inner = Bounds::new(b.x + half, b.y + half, b.width - width, b.height - width);
+                let inner = (inner.width > 0.0 && inner.height > 0.0).then(|| rectangle(inner));
+                paint(
+                    image,
+                    &ring(rectangle(outer), inner),
+                    style.stroke,
+                    style.opacity,
+                    BlendMode::Over,
+                );
+            }
+            ShapeKind::Ellipse { bounds } => {
+                let b = self.bounds(*bounds).normalized();
+                let (center, rx, ry) = (b.center(), b.width / 2.0, b.height / 2.0);
+                if let Some(fill) = style.fill {
+                    paint(
+                        image,
+                        &ring(ellipse(center, rx, ry), None),
+                        fill,
+                        style.opacity,
+                        BlendMode::Over,
+                    );
+                }
+
+                let half = width / 2.0;
+                let inner = (rx > half && ry > half).then(|| ellipse(center, rx - half, ry - half));
+                let outline = ring(ellipse(center, rx + half, ry + half), inner);
+                paint(
+                    image,
+                    &outline,
+                    style.stroke,
+                    style.opacity,
+                    BlendMode::Over,
+                );
+            }
+            ShapeKind::Line { from, to } => {
+                let outline = polyline(&[self.point(*from), self.point(*to)], width);
+                paint(
+                    image,
+                    &outline,
+                    style.stroke,
+                    style.opacity,
+                    BlendMode::Over,
+                );
+            }
+            ShapeKind::Arrow {
+                from,
+                to,
+                control,
+                double,
+                tapered,
+            } => {
+                let outline = arrow(
+                    self.point(*from),
+                    self.point(*to),
+                    control.map(|c| self.point(c)),
+                    width,
+                    *double,
+                    *tapered,
+                );
+                paint(
+                    image,
+                    &outline,
+                    style.stroke,
+                    style.opacity,
+                    BlendMode::Over,
+                );
+            }
+            ShapeKind::Freehand { points } => {
+                let points: Vec<Point> = points.iter().map(|p| self.point(*p)).collect();
+                paint(
+                    image,
+                    &polyline(&points, width),
+                    style.stroke,
+                    style.opacity,
+                    BlendMode::Over,
+                );
+            }
+            ShapeKind::Highlighter { points } => {
+                // 正片叠底，文字在荧光笔下仍然清晰
+                let points: Vec<Point> = points.iter().map(|p| self.point(*p)).collect();
+                let opacity = style.opacity * HIGHLIGHTER_OPACITY;
+                paint(
+                    image,
+                    &polyline(&points, width),
+                    style.stroke,
+                    opacity,
+                    BlendMode::Multiply,
+                );
+            }
+            ShapeKind::Text {
+                position,
+                text,
+                size,
+                max_width,
+                outline,
+            } => {
+                let text_style = text_style(*size, *max_width, *outline, style, self.scale);
+                let block = self.fonts.render(text, &text_style);
+                let p = self.point(*position);
+                let corner = Point::new(p.x - block.origin.x, p.y - block.origin.y);
+                blend_rotated(image, &block.image, corner, pivot, rotation, style.opacity);
+            }
+            ShapeKind::Callout {
+                bounds,
+                target,
+                text,
+                size,
+            } => {
+                let b = self.bounds(*bounds).normalized();
+                let size = size * self.scale;
+                let contour = callout(b, self.point(*target), size * CALLOUT_PADDING);
+                let background = style.fill.unwrap_or(Color::rgb(255, 255, 255));
+                paint(
+                    image,
+                    &ring(contour.clone(), None),
+                    background,
+                    style.opacity,
+                    BlendMode::Over,
+                );
+
+                // 闭合折线作为边框
+                let mut border = contour;
+                border.push(border[0]);
+                paint(
+                    image,
+                    &polyline(&border, width),
+                    style.stroke,
+                    style.opacity,
+                    BlendMode::Over,
+                );
+
+                let padding = size * CALLOUT_PADDING;
+                let text_style = callout_text_style(size, b.width, style);
+                let label = self.fonts.render(text, &text_style).image;
+                let corner = Point::new(b.x + padding, b.y + padding);
+                blend_rotated(image, &label, corner, pivot, rotation, style.opacity);
+            }
+            ShapeKind::Spotlight {
+                areas,
+                effect,
+                feather,
+            } => {
+                let areas: Vec<SpotlightArea> = areas
+                    .iter()
+                    .map(|area| match area {
+                        SpotlightArea::Rectangle(b) => SpotlightArea::Rectangle(self.bounds(*b)),
+                        SpotlightArea::Ellipse(b) => SpotlightArea::Ellipse(self.bounds(*b)),
+                    })
+                    .collect();
+                let effect = match *effect {
+                    SpotlightEffect::Blur { radius } => SpotlightEffect::Blur {
+                        radius: radius * self.scale,
+                    },
+                    dim => dim,
+                };
+                spotlight(image, &areas, effect, feather * self.scale, style.opacity);
+            }
+            ShapeKind::Counter {
+                center,
+                number,
+                radius,
+            } => {
+                let c = self.point(*center);
+                let r = radius * self.scale;
+                let background = style.fill.unwrap_or(style.stroke);
+                paint(
+                    image,
+                    &ring(ellipse(c, r, r), None),
+                    background,
+                    style.opacity,
+                    BlendMode::Over,
+                );
+
+                let text_style = TextStyle {
+                    size: r * 1.2,
+                    color: Color::rgb(255, 255, 255),
+                    ..TextStyle::default()
+                };
+                let label = self.fonts.render(&number.to_string(), &text_style).image;
+                let x = (c.x - label.width() as f32 / 2.0).round() as i32;
+                let y = (c.y - label.height() as f32 / 2.0).round() as i32;
+                image.blend(&label.view(), x, y, BlendMode::Over, style.opacity);
+            }
+        }
+    }
+
+    /// 光栅化 outline 并按覆盖率把 color 混合到 image 上
+    fn paint(
+        &self,
+        image: &mut Image,
+        outline: &Outline,
+        color: Color,
+        opacity: f32,
+        mode: BlendMode,
+    ) {
+        if outline.is_empty() {
+            return;
+        }
+
+        let (left, top, right, bottom) = match outline.bounds() {
+            Some(bounds) => bounds,
+            None => return,
+        };
+        let left = left.max(0);
+        let top = top.max(0);
+        let right = right.min(image.width() as i32);
+        let bottom = bottom.min(image.height() as i32);
+        if left >= right || top >= bottom {
+            return;
+        }
+
+        let (width, height) = ((right - left) as u32, (bottom - top) as u32);
+        let mut rasterizer = Rasterizer::new(left, top, width, height);
+        for fill in &outline.fills {
+            rasterizer.fill(fill);
+        }
+        for hole in &outline.holes {
+            rasterizer.cut(hole);
+        }
+
+        let src = color.to_array();
+        let coverage = rasterizer.coverage();
+        let stride = image.width() as usize * 4;
+        let rgba = image.rgba_mut();
+        for y in 0..height as usize {
+            let start = (top as usize + y) * stride + left as usize * 4;
+            let row = &mut rgba[start..start + width as usize * 4];
+
+            for (x, dst) in row.chunks_exact_mut(4).enumerate() {
+                let c = coverage[y * width as usize + x];
+                if c > 0.0 {
+                    blend_pixel(dst, &src, mode, opacity * c, AlphaMode::Straight);
+                }
+            }
+        }
+    }
+}
+
+/// 把左上角在 corner 的 label 绕 pivot 旋转后混合到 image 上
+fn blend_rotated(
+    image: &mut Image,
+    label: &Image,
+    corner: Point,
+    pivot: Point,
+    rotation: f32,
+    opacity: f32,
+) {
+    let (w, h) = (label.width() as f32, label.height() as f32);
+    let center = rotate_point(
+        Point::new(corner.x + w / 2.0, corner.y + h / 2.0),
+        pivot,
+        rotation,
+    );
+    let label = label.rotate(rotation);
+    let x = (center.x - label.width() as f32 / 2.0).round() as i32;
+    let y = (center.y - label.height() as f32 / 2.0).round() as i32;
+    image.blend(&label.view(), x, y, BlendMode::Over, opacity);
+}
+
+/// 以 1 倍比例把标注合成到底图上
+pub fn flatten(document: &AnnotationDocument) -> Image {
+    Renderer::default().render(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document() -> AnnotationDocument {
+        AnnotationDocument::new(Image::new(20, 20, [255, 255, 255, 255].repeat(400)))
+    }
+
+    #[test]
+    fn rectangle_stroke_and_fill() {
+        let mut document = document();
+        let style = Style {
+            stroke: Color::rgb(255, 0, 0),
+            fill: Some(Color::rgb(0, 0, 255)),
+            width: 2.0,
+            opacity: 1.0,
+        };
+        let bounds = Bounds::new(4.0, 4.0, 10.0, 10.0);
+        document.add(ShapeKind::Rectangle { bounds }, style);
+        let image = flatten(&document);
+
+        assert_eq!(image.pixel(4, 4), Some(Color::rgb(255, 0, 0)));
+        assert_eq!(image.pixel(3, 8), Some(Color::rgb(255, 0, 0)));
+        assert_eq!(image.pixel(9, 9), Some(Color::rgb(0, 0, 255)));
+        assert_eq!(image.pixel(1, 1), Some(Color::rgb(255, 255, 255)));
+    }
+
+    #[test]
+    fn scaled_render_matches_golden_checksum() {
+        let mut document = document();
+        let points = vec![
+            Point::new(2.0, 2.0),
+            Point::new(10.0, 15.0),
+            Point::new(18.0, 3.0),
+        ];
+        document.add(ShapeKind::Freehand { points }, Style::default());
+        document.add(
+            ShapeKind::Arrow {
+                from: Point::new(1.0, 18.0),
+                to: Point::new(18.0, 10.0),
+                control: None,
+                double: false,
+                tapered: true,
+            },
+            Style::default(),
+        );
+
+        let ellipse = document.add(
+            ShapeKind::Ellipse {
+                bounds: Bounds::new(3.0, 4.0, 11.0, 7.0),
+            },
+            Style {
+                width: 1.5,
+                ..Style::default()
+            },
+        );
+        document.get_mut(ellipse).unwrap().rotation = 33.0;
+        document.add(
+            ShapeKind::Spotlight {
+                areas: vec![SpotlightArea::Ellipse(Bounds::new(5.0, 5.0, 9.0, 9.0))],
+                effect: SpotlightEffect::Blur { radius: 2.0 },
+                feather: 3.0,
+            },
+            Style::default(),
+        );
+
+        let a = Renderer::new(2.0).render(&document);
+        assert_eq!((a.width(), a.height()), (40, 40));
+        // 折线经过 (10, 15)，放大两倍后在 (20, 30)
+        assert_eq!(a.pixel(20, 30), Some(Style::default().stroke));
+
+        // 曲线细分、旋转、羽化都用 libm 计算，任何平台上都必须得到同样的像素，
+        // 修改绘制算法时需要同时更新这里
+        let checksum = a.rgba().iter().fold(0xcbf2_9ce4_8422_2325u64, |h, b| {
+            (h ^ *b as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+        assert_eq!(checksum, 0xfebe_305a_af3d_f67b);
+    }
+
+    #[test]
+    fn highlighter_multiplies() {
+        let mut document = AnnotationDocument::new(Image::new(10, 10, [0, 0, 0, 255].repeat(100)));
+        let style = Style {
+            stroke: Color::rgb(255, 255, 0),
+            width: 6.0,
+            ..Style::default()
+        };
+        let points = vec![Point::new(0.0, 5.0), Point::new(10.0, 5.0)];
+        document.add(ShapeKind::Highlighter { points }, style);
+
+        // 黑色正片叠底后仍是黑色
+        assert_eq!(flatten(&document).pixel(5, 5), Some(Color::rgb(0, 0, 0)));
+    }
+}
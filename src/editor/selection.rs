@@ -0,0 +1,446 @@
+use crate::core::image::Rect;
+use crate::editor::annotation::{Bounds, Point};
+use crate::editor::geometry::distance;
+use crate::editor::transform::{box_handles, drag_box_handle, Handle};
+
+/// 点中手柄允许的误差
+pub const HANDLE_TOLERANCE: f32 = 6.0;
+
+/// 宽高都小于这个值的拖动视为单击，不产生选区
+pub const MIN_DRAG: f32 = 3.0;
+
+/// 按住 shift 时方向键一次移动的距离
+pub const NUDGE_STEP: i32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Left,
+    Right,
+    Up,
+    Down,
+    Enter,
+    Escape,
+}
+
+/// 选区交互的输入事件，坐标均为全局坐标
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelectionEvent {
+    PointerDown(Point),
+    PointerMove(Point),
+    PointerUp(Point),
+    Key {
+        key: Key,
+        shift: bool,
+    },
+    /// 固定宽高比，None 为自由比例
+    AspectRatio(Option<f32>),
+    /// 输入精确尺寸，保持左上角不动
+    Size {
+        width: u32,
+        height: u32,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelectionStatus {
+    Active,
+    Confirmed(Rect),
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Drag {
+    None,
+    /// 从 anchor 拖出新选区，previous 为按下前的选区
+    Create {
+        anchor: Point,
+        previous: Option<Bounds>,
+    },
+    /// 拖动整个选区，offset 为按下位置到左上角的距离
+    Move {
+        offset: Point,
+    },
+    /// 拖动手柄，start 为按下时的选区
+    Resize {
+        handle: Handle,
+        start: Bounds,
+    },
+}
+
+/// 框选截图区域的交互状态机，不依赖任何界面库
+///
+/// 调用方把鼠标、键盘事件依次交给 `handle`，根据 `selection` 绘制选区，
+/// 按回车得到全局坐标的 `Rect`，可以直接传给 `Screen::capture_area`（需先减去屏幕原点）
+///
+/// ```text
+/// 空白处按下拖动   新建选区
+/// 选区内按下拖动   移动选区
+/// 手柄上按下拖动   调整大小，拖过对边时翻转
+/// 方向键          移动 1 像素，按住 shift 移动 10 像素
+/// 回车 / Esc      确认 / 取消
+/// ```
+#[derive(Debug, Clone)]
+pub struct SelectionSession {
+    desktop: Rect,
+    selection: Option<Bounds>,
+    aspect: Option<f32>,
+    drag: Drag,
+    pointer: Point,
+    status: SelectionStatus,
+}
+
+fn to_bounds(rect: Rect) -> Bounds {
+    Bounds::new(
+        rect.x as f32,
+        rect.y as f32,
+        rect.width as f32,
+        rect.height as f32,
+    )
+}
+
+fn contains(bounds: Bounds, p: Point) -> bool {
+    let b = bounds.normalized();
+    p.x >= b.x && p.x <= b.x + b.width && p.y >= b.y && p.y <= b.y + b.height
+}
+
+impl SelectionSession {
+    /// desktop 为所有屏幕拼成的全局区域，选区不会超出它
+    pub fn new(desktop: Rect) -> Self {
+        SelectionSession {
+            desktop,
+            selection: None,
+            aspect: None,
+            drag: Drag::None,
+            pointer: Point::new(desktop.x as f32, desktop.y as f32),
+            status: SelectionStatus::Active,
+        }
+    }
+
+    pub fn status(&self) -> SelectionStatus {
+        self.status
+    }
+
+    pub fn aspect_ratio(&self) -> Option<f32> {
+        self.aspect
+    }
+
+    /// 当前选区，取整到像素并裁剪到 desktop 内
+    pub fn selection(&self) -> Option<Rect> {
+        let b = self.selection?.normalized();
+        let (left, top) = (b.x.round() as i32, b.y.round() as i32);
+        let right = (b.x + b.width).round() as i32;
+        let bottom = (b.y + b.height).round() as i32;
+        if right <= left || bottom <= top {
+            return None;
+        }
+        Rect::new(left, top, (right - left) as u32, (bottom - top) as u32).intersect(&self.desktop)
+    }
+
+    /// 是否正在拖动
+    pub fn is_dragging(&self) -> bool {
+        !matches!(self.drag, Drag::None)
+    }
+
+    /// point 处的手柄，用于切换鼠标指针
+    pub fn handle_at(&self, point: Point) -> Option<Handle> {
+        let selection = self.selection?;
+        box_handles(selection)
+            .into_iter()
+            .find(|(_, p)| distance(*p, point) <= HANDLE_TOLERANCE)
+            .map(|(h, _)| h)
+    }
+
+    /// 依次处理一串事件，返回最后的状态
+    pub fn feed<I: IntoIterator<Item = SelectionEvent>>(&mut self, events: I) -> SelectionStatus {
+        for event in events {
+            self.handle(event);
+        }
+        self.status
+    }
+
+    /// 处理一个事件，确认或取消后忽略后续事件
+    pub fn handle(&mut self, event: SelectionEvent) -> SelectionStatus {
+        if self.status != SelectionStatus::Active {
+            return self.status;
+        }
+
+        match event {
+            SelectionEvent::PointerDown(p) => self.pointer_down(self.clamp(p)),
+            SelectionEvent::PointerMove(p) => self.pointer_move(self.clamp(p)),
+            SelectionEvent::PointerUp(p) => {
+                self.pointer_move(self.clamp(p));
+                self.pointer_up();
+            }
+            SelectionEvent::Key { key, shift } => self.key(key, shift),
+            SelectionEvent::AspectRatio(aspect) => self.set_aspect_ratio(aspect),
+            SelectionEvent::Size { width, height } => self.set_size(width, height),
+        }
+        self.status
+    }
+
+    /// 修改宽高比，已有选区保持左上角和宽度，调整高度；超出 desktop 时以左上角为中心按比例缩小
+    pub fn set_aspect_ratio(&mut self, aspect: Option<f32>) {
+        self.aspect = aspect.filter(|a| a.is_finite() && *a > 0.0);
+        if let (Some(aspect), Some(b)) = (self.aspect, self.selection) {
+            let b = b.normalized();
+            let fitted = self.fit(
+                Bounds::new(b.x, b.y, b.width, b.width / aspect),
+                Point::new(b.x, b.y),
+            );
+            self.set_bounds(fitted);
+        }
+    }
+
+    /// 设置精确尺寸（忽略宽高比），保持左上角不动；还没有选区时以指针位置为左上角，
+    /// 超出 desktop 时向左上方平移，仍放不下则缩小
+    pub fn set_size(&mut self, width: u32, height: u32) {
+        let (x, y) = match self.selection {
+            Some(b) => {
+                let b = b.normalized();
+                (b.x, b.y)
+            }
+            None => (self.pointer.x, self.pointer.y),
+        };
+        let width = (width as f32).min(self.desktop.width as f32);
+        let height = (height as f32).min(self.desktop.height as f32);
+        self.drag = Drag::None;
+        self.set_bounds(Bounds::new(x, y, width, height));
+    }
+
+    fn clamp(&self, p: Point) -> Point {
+        let d = to_bounds(self.desktop);
+        Point::new(
+            p.x.clamp(d.x, d.x + d.width),
+            p.y.clamp(d.y, d.y + d.height),
+        )
+    }
+
+    /// 平移到 desktop 内，放不下的方向贴住左上边
+    fn set_bounds(&mut self, bounds: Bounds) {
+        let d = to_bounds(self.desktop);
+        let mut b = bounds.normalized();
+        b.x = b.x.min(d.x + d.width - b.width).max(d.x);
+        b.y = b.y.min(d.y + d.height - b.height).max(d.y);
+        self.selection = Some(b);
+    }
+
+    fn pointer_down(&mut self, p: Point) {
+        self.pointer = p;
+        self.drag = match (self.handle_at(p), self.selection) {
+            (Some(handle), Some(start)) => Drag::Resize {
+                handle,
+                start: start.normalized(),
+            },
+            (None, Some(b)) if contains(b, p) => {
+                let b = b.normalized();
+                Drag::Move {
+                    offset: Point::new(p.x - b.x, p.y - b.y),
+                }
+            }
+            _ => Drag::Create {
+                anchor: p,
+                previous: self.selection,
+            },
+        };
+    }
+
+    fn pointer_move(&mut self, p: Point) {
+        self.pointer = p;
+        match self.drag {
+            Drag::None => {}
+            Drag::Create { anchor, .. } => {
+                let start = Bounds::new(anchor.x, anchor.y, 0.0, 0.0);
+                let bounds = drag_box_handle(start, Handle::BottomRight, p, self.aspect);
+                self.selection = Some(self.fit(bounds, anchor));
+            }
+            Drag::Move { offset } => {
+                if let Some(b) = self.selection {
+                    self.set_bounds(Bounds::new(
+                        p.x - offset.x,
+                        p.y - offset.y,
+                        b.width,
+                        b.height,
+                    ));
+                }
+            }
+            Drag::Resize { handle, start } => {
+                // 对边（对角）不动
+                let (ax, ay) = handle.anchor().unwrap_or((0.5, 0.5));
+                let anchor = Point::new(
+                    start.x + start.width * (1.0 - ax),
+                    start.y + start.height * (1.0 - ay),
+                );
+                let bounds = drag_box_handle(start, handle, p, self.aspect);
+                self.selection = Some(self.fit(bounds, anchor));
+            }
+        }
+    }
+
+    fn pointer_up(&mut self) {
+        // 拖动距离太小视为单击，恢复原来的选区
+        if let Drag::Create { previous, .. } = self.drag {
+            let dragged = matches!(
+                self.selection,
+                Some(b) if b.width >= MIN_DRAG || b.height >= MIN_DRAG
+            );
+            if !dragged {
+                self.selection = previous;
+            }
+        }
+        self.drag = Drag::None;
+    }
+
+    /// 裁剪到 desktop 内；固定比例时改为以 anchor 为中心等比缩小，保持比例不变
+    fn fit(&self, bounds: Bounds, anchor: Point) -> Bounds {
+        let d = to_bounds(self.desktop);
+        let b = bounds.normalized();
+        let (right, bottom) = (b.x + b.width, b.y + b.height);
+        let (d_right, d_bottom) = (d.x + d.width, d.y + d.height);
+
+        if self.aspect.is_none() {
+            let (left, top) = (b.x.max(d.x), b.y.max(d.y));
+            return Bounds::new(
+                left,
+                top,
+                (right.min(d_right) - left).max(0.0),
+                (bottom.min(d_bottom) - top).max(0.0),
+            );
+        }
+
+        let mut s = 1f32;
+        if b.x < d.x {
+            s = s.min((anchor.x - d.x) / (anchor.x - b.x));
+        }
+        if right > d_right {
+            s = s.min((d_right - anchor.x) / (right - anchor.x));
+        }
+        if b.y < d.y {
+            s = s.min((anchor.y - d.y) / (anchor.y - b.y));
+        }
+        if bottom > d_bottom {
+            s = s.min((d_bottom - anchor.y) / (bottom - anchor.y));
+        }
+        Bounds::new(
+            anchor.x + (b.x - anchor.x) * s,
+            anchor.y + (b.y - anchor.y) * s,
+            b.width * s,
+            b.height * s,
+        )
+    }
+
+    fn key(&mut self, key: Key, shift: bool) {
+        let step = if shift { NUDGE_STEP as f32 } else { 1.0 };
+        let (dx, dy) = match key {
+            Key::Left => (-step, 0.0),
+            Key::Right => (step, 0.0),
+            Key::Up => (0.0, -step),
+            Key::Down => (0.0, step),
+            Key::Enter => {
+                if let Some(rect) = self.selection() {
+                    self.drag = Drag::None;
+                    self.status = SelectionStatus::Confirmed(rect);
+                }
+                return;
+            }
+            Key::Escape => {
+                self.drag = Drag::None;
+                self.status = SelectionStatus::Cancelled;
+                return;
+            }
+        };
+
+        if self.is_dragging() {
+            return;
+        }
+        if let Some(b) = self.selection {
+            let b = b.normalized();
+            self.set_bounds(Bounds::new(b.x + dx, b.y + dy, b.width, b.height));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use SelectionEvent::{AspectRatio, PointerDown, PointerMove, PointerUp, Size};
+
+    // 两块屏幕，副屏在主屏左侧
+    const DESKTOP: Rect = Rect {
+        x: -1920,
+        y: 0,
+        width: 3840,
+        height: 1080,
+    };
+
+    fn drag(from: (f32, f32), to: (f32, f32)) -> [SelectionEvent; 3] {
+        [
+            PointerDown(Point::new(from.0, from.1)),
+            PointerMove(Point::new((from.0 + to.0) / 2.0, (from.1 + to.1) / 2.0)),
+            PointerUp(Point::new(to.0, to.1)),
+        ]
+    }
+
+    fn key(key: Key, shift: bool) -> SelectionEvent {
+        SelectionEvent::Key { key, shift }
+    }
+
+    #[test]
+    fn drag_move_nudge_and_confirm() {
+        let mut session = SelectionSession::new(DESKTOP);
+        // 从右下往左上拖，跨过两块屏幕
+        session.feed(drag((100.0, 300.0), (-50.0, 200.0)));
+        assert_eq!(session.selection(), Some(Rect::new(-50, 200, 150, 100)));
+
+        // 在选区内拖动是移动，不能移出桌面
+        session.feed(drag((0.0, 250.0), (0.0, -1000.0)));
+        assert_eq!(session.selection(), Some(Rect::new(-50, 0, 150, 100)));
+
+        session.feed([key(Key::Right, false), key(Key::Down, true)]);
+        assert_eq!(session.selection(), Some(Rect::new(-49, 10, 150, 100)));
+
+        // 单击空白处不会丢掉选区
+        session.feed(drag((1000.0, 1000.0), (1001.0, 1000.0)));
+        let status = session.feed([key(Key::Enter, false), key(Key::Left, false)]);
+        assert_eq!(
+            status,
+            SelectionStatus::Confirmed(Rect::new(-49, 10, 150, 100))
+        );
+    }
+
+    #[test]
+    fn resize_with_handles_and_aspect_ratio() {
+        let mut session = SelectionSession::new(DESKTOP);
+        session.feed([AspectRatio(Some(2.0))]);
+        session.feed(drag((0.0, 100.0), (100.0, 110.0)));
+        assert_eq!(session.selection(), Some(Rect::new(0, 100, 100, 50)));
+
+        // 拖左边，高度跟着变，上下以中心为准
+        assert_eq!(
+            session.handle_at(Point::new(2.0, 124.0)),
+            Some(Handle::Left)
+        );
+        session.feed(drag((0.0, 125.0), (-100.0, 125.0)));
+        assert_eq!(session.selection(), Some(Rect::new(-100, 75, 200, 100)));
+
+        // 拖出桌面时以对角为中心按比例缩小
+        session.feed(drag((100.0, 175.0), (3000.0, 700.0)));
+        assert_eq!(session.selection(), Some(Rect::new(-100, 75, 2010, 1005)));
+
+        session.feed([
+            AspectRatio(None),
+            Size {
+                width: 640,
+                height: 480,
+            },
+        ]);
+        assert_eq!(session.selection(), Some(Rect::new(-100, 75, 640, 480)));
+
+        // 改成竖长的比例时高度放不下，以左上角为中心缩小，再往上挪一格
+        session.feed([AspectRatio(Some(0.5)), key(Key::Up, false)]);
+        assert_eq!(session.selection(), Some(Rect::new(-100, 74, 503, 1005)));
+        assert_eq!(
+            session.feed([key(Key::Escape, false)]),
+            SelectionStatus::Cancelled
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use crate::core::image::Image;
+use crate::editor::annotation::{SpotlightArea, SpotlightEffect};
+use crate::editor::geometry::{ellipse, rectangle};
+use crate::editor::raster::Rasterizer;
+
+/// 区域覆盖率，区域内为 1.0，feather 大于 0 时边缘按高斯分布过渡
+pub fn mask(width: u32, height: u32, areas: &[SpotlightArea], feather: f32) -> Vec<f32> {
+    let mut rasterizer = Rasterizer::new(0, 0, width, height);
+    for area in areas {
+        let b = area.bounds().normalized();
+        match area {
+            SpotlightArea::Rectangle(_) => rasterizer.fill(&rectangle(b)),
+            SpotlightArea::Ellipse(_) => {
+                rasterizer.fill(&ellipse(b.center(), b.width / 2.0, b.height / 2.0))
+            }
+        }
+    }
+
+    let coverage = rasterizer.coverage();
+    if feather <= 0.0 {
+        return coverage;
+    }
+    feather_mask(&coverage, width as usize, height as usize, feather)
+}
+
+/// 对覆盖率做可分离的高斯模糊，feather 约为 3 个标准差
+fn feather_mask(mask: &[f32], width: usize, height: usize, feather: f32) -> Vec<f32> {
+    let radius = feather.ceil() as i32;
+    let sigma = (feather / 3.0).max(0.5);
+    let kernel: Vec<f32> = (-radius..=radius)
+        .map(|i| libm::expf(-((i * i) as f32) / (2.0 * sigma * sigma)))
+        .collect();
+    let total: f32 = kernel.iter().sum();
+
+    let pass = |src: &[f32], horizontal: bool| -> Vec<f32> {
+        let mut dst = vec![0f32; src.len()];
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                let mut sum = 0f32;
+                for (k, w) in kernel.iter().enumerate() {
+                    let offset = k as i32 - radius;
+                    let (sx, sy) = if horizontal {
+                        ((x + offset).clamp(0, width as i32 - 1), y)
+                    } else {
+                        (x, (y + offset).clamp(0, height as i32 - 1))
+                    };
+                    sum += src[sy as usize * width + sx as usize] * w;
+                }
+                dst[y as usize * width + x as usize] = sum / total;
+            }
+        }
+        dst
+    };
+
+    pass(&pass(mask, true), false)
+}
+
+/// 在 image 上应用聚光灯，strength 为效果强度 0.0 ~ 1.0
+pub fn spotlight(
+    image: &mut Image,
+    areas: &[SpotlightArea],
+    effect: SpotlightEffect,
+    feather: f32,
+    strength: f32,
+) {
+    let (width, height) = (image.width(), image.height());
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let mask = mask(width, height, areas, feather);
+    match effect {
+        SpotlightEffect::Dim { opacity } => {
+            let amount = (opacity * strength).clamp(0.0, 1.0);
+            for (pixel, inside) in image.rgba_mut().chunks_exact_mut(4).zip(&mask) {
+                let factor = 1.0 - amount * (1.0 - inside);
+                for c in &mut pixel[..3] {
+                    *c = (*c as f32 * factor).round() as u8;
+                }
+            }
+        }
+        SpotlightEffect::Blur { radius } => {
+            let blurred = image.blur(radius.round().max(0.0) as u32);
+            let strength = strength.clamp(0.0, 1.0);
+            let pixels = image.rgba_mut().chunks_exact_mut(4);
+            for ((pixel, blurred), inside) in pixels.zip(blurred.rgba().chunks_exact(4)).zip(&mask)
+            {
+                let t = strength * (1.0 - inside);
+                for (c, b) in pixel.iter_mut().zip(blurred) {
+                    *c = (*c as f32 + (*b as f32 - *c as f32) * t).round() as u8;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::image::Color;
+    use crate::editor::annotation::Bounds;
+
+    #[test]
+    fn dims_outside_with_feathered_edge() {
+        let mut image = Image::new(40, 40, [200, 200, 200, 255].repeat(1600));
+        let areas = [SpotlightArea::Rectangle(Bounds::new(
+            10.0, 10.0, 20.0, 20.0,
+        ))];
+        spotlight(
+            &mut image,
+            &areas,
+            SpotlightEffect::Dim { opacity: 0.5 },
+            4.0,
+            1.0,
+        );
+
+        assert_eq!(image.pixel(20, 20), Some(Color::rgb(200, 200, 200)));
+        assert_eq!(image.pixel(1, 1), Some(Color::rgb(100, 100, 100)));
+        // 边缘处介于两者之间
+        let edge = image.pixel(10, 20).unwrap();
+        assert!(edge.r > 100 && edge.r < 200);
+    }
+
+    #[test]
+    fn blur_keeps_inside_sharp() {
+        let rgba: Vec<u8> = (0..1600)
+            .flat_map(|i| {
+                let v = if (i % 40 + i / 40) % 2 == 0 { 0 } else { 255 };
+                [v, v, v, 255]
+            })
+            .collect();
+        let mut image = Image::new(40, 40, rgba.clone());
+        let areas = [SpotlightArea::Ellipse(Bounds::new(10.0, 10.0, 20.0, 20.0))];
+        spotlight(
+            &mut image,
+            &areas,
+            SpotlightEffect::Blur { radius: 3.0 },
+            0.0,
+            1.0,
+        );
+
+        let original = Image::new(40, 40, rgba);
+        assert_eq!(image.pixel(20, 20), original.pixel(20, 20));
+        let blurred = image.pixel(2, 2).unwrap();
+        assert!(blurred.r > 64 && blurred.r < 192);
+    }
+}
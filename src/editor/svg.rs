@@ -0,0 +1,495 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use crate::core::image::Color;
+use crate::editor::annotation::{
+    AnnotationDocument, Point, Shape, ShapeKind, SpotlightArea, SpotlightEffect,
+};
+use crate::editor::geometry::{arrow, callout, Outline};
+use crate::editor::raster::signed_area;
+use crate::editor::render::{callout_text_style, text_style, CALLOUT_PADDING, HIGHLIGHTER_OPACITY};
+use crate::editor::text::{FontSet, TextStyle};
+use crate::editor::transform::local_bounds;
+
+/// SVG 导出：底图以 base64 PNG 内嵌，标注导出为矢量元素，可以在 Inkscape、Figma 中继续编辑
+///
+/// 几何形状与 `Renderer` 共用同一套生成代码，文字使用同样的排版结果分行，
+/// 所以在装有相同字体的环境里看起来与位图一致
+#[derive(Default)]
+pub struct SvgExporter {
+    /// 用于文字换行，并作为 font-family 写入 SVG
+    pub fonts: FontSet,
+}
+
+/// 保留两位小数，去掉末尾的 0
+fn num(value: f32) -> String {
+    let text = format!("{:.2}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => "0".to_string(),
+        text => text.to_string(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// `fill="#RRGGBB"`，半透明时附带 `fill-opacity`
+fn paint(attribute: &str, color: Color) -> String {
+    let hex = Color { a: 255, ..color }.to_hex();
+    if color.a == 255 {
+        format!(r#"{attribute}="{hex}""#)
+    } else {
+        let opacity = num(color.a as f32 / 255.0);
+        format!(r#"{attribute}="{hex}" {attribute}-opacity="{opacity}""#)
+    }
+}
+
+fn opacity(value: f32) -> String {
+    if value < 1.0 {
+        format!(r#" opacity="{}""#, num(value))
+    } else {
+        String::new()
+    }
+}
+
+fn points(points: &[Point]) -> String {
+    points
+        .iter()
+        .map(|p| format!("{},{}", num(p.x), num(p.y)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn contour(d: &mut String, points: &[Point], positive: bool) {
+    if points.len() < 3 {
+        return;
+    }
+
+    // 统一方向后按非零规则合并，与光栅化结果相同
+    let mut ordered: Vec<Point> = points.to_vec();
+    if (signed_area(points) >= 0.0) != positive {
+        ordered.reverse();
+    }
+    for (i, p) in ordered.iter().enumerate() {
+        let command = if i == 0 { 'M' } else { 'L' };
+        let _ = write!(d, "{command}{} {} ", num(p.x), num(p.y));
+    }
+    d.push('Z');
+}
+
+fn path_data(outline: &Outline) -> String {
+    let mut d = String::new();
+    for fill in &outline.fills {
+        contour(&mut d, fill, true);
+    }
+    for hole in &outline.holes {
+        contour(&mut d, hole, false);
+    }
+    d
+}
+
+impl SvgExporter {
+    pub fn with_fonts(mut self, fonts: FontSet) -> Self {
+        self.fonts = fonts;
+        self
+    }
+
+    fn font_family(&self) -> String {
+        let mut families: Vec<String> = self
+            .fonts
+            .family_names()
+            .iter()
+            .map(|name| format!("'{}'", escape(name)))
+            .collect();
+        families.push("sans-serif".to_string());
+        families.join(", ")
+    }
+
+    /// 多行文字，(x, y) 为文字块左上角
+    fn text(&self, text: &str, style: &TextStyle, x: f32, y: f32) -> String {
+        let layout = self.fonts.layout(text, style);
+        let mut svg = String::new();
+
+        if let Some(background) = style.background {
+            let p = background.padding;
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" rx="{}" {}/>"#,
+                num(x - p),
+                num(y - p),
+                num(layout.width + p * 2.0),
+                num(layout.height + p * 2.0),
+                num(background.radius),
+                paint("fill", background.color),
+            );
+        }
+
+        // 描边画在文字下面，宽度是外扩宽度的两倍
+        let outline = match style.outline {
+            Some(outline) => format!(
+                r#" {} stroke-width="{}" stroke-linejoin="round" paint-order="stroke""#,
+                paint("stroke", outline.color),
+                num(outline.width * 2.0)
+            ),
+            None => String::new(),
+        };
+        let _ = write!(
+            svg,
+            r#"<text font-family="{}" font-size="{}" {}{} xml:space="preserve">"#,
+            self.font_family(),
+            num(style.size),
+            paint("fill", style.color),
+            outline,
+        );
+        for line in &layout.lines {
+            let _ = write!(
+                svg,
+                r#"<tspan x="{}" y="{}">{}</tspan>"#,
+                num(x + line.x),
+                num(y + line.baseline),
+                escape(&text[line.range.clone()]),
+            );
+        }
+        svg.push_str("</text>\n");
+        svg
+    }
+
+    fn shape(&self, shape: &Shape) -> String {
+        let style = &shape.style;
+        let alpha = opacity(style.opacity);
+        let fill = style
+            .fill
+            .map(|fill| paint("fill", fill))
+            .unwrap_or_else(|| r#"fill="none""#.to_string());
+        let stroke = format!(
+            r#"{} stroke-width="{}""#,
+            paint("stroke", style.stroke),
+            num(style.width)
+        );
+
+        match &shape.kind {
+            ShapeKind::Rectangle { bounds } => {
+                let b = bounds.normalized();
+                format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" {fill} {stroke}{alpha}/>"#,
+                    num(b.x),
+                    num(b.y),
+                    num(b.width),
+                    num(b.height),
+                ) + "\n"
+            }
+            ShapeKind::Ellipse { bounds } => {
+                let b = bounds.normalized();
+                let c = b.center();
+                format!(
+                    r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}" {fill} {stroke}{alpha}/>"#,
+                    num(c.x),
+                    num(c.y),
+                    num(b.width / 2.0),
+                    num(b.height / 2.0),
+                ) + "\n"
+            }
+            ShapeKind::Line { from, to } => {
+                format!(
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}" {stroke} stroke-linecap="round"{alpha}/>"#,
+                    num(from.x),
+                    num(from.y),
+                    num(to.x),
+                    num(to.y),
+                ) + "\n"
+            }
+            ShapeKind::Arrow {
+                from,
+                to,
+                control,
+                double,
+                tapered,
+            } => {
+                let outline = arrow(*from, *to, *control, style.width, *double, *tapered);
+                format!(
+                    r#"<path d="{}" {}{alpha}/>"#,
+                    path_data(&outline),
+                    paint("fill", style.stroke)
+                ) + "\n"
+            }
+            ShapeKind::Freehand { points: line } => {
+                format!(
+                    r#"<polyline points="{}" fill="none" {stroke} stroke-linecap="round" stroke-linejoin="round"{alpha}/>"#,
+                    points(line),
+                ) + "\n"
+            }
+            ShapeKind::Highlighter { points: line } => {
+                format!(
+                    r#"<polyline points="{}" fill="none" {stroke} stroke-linecap="round" stroke-linejoin="round" style="mix-blend-mode:multiply"{}/>"#,
+                    points(line),
+                    opacity(style.opacity * HIGHLIGHTER_OPACITY),
+                ) + "\n"
+            }
+            ShapeKind::Text {
+                position,
+                text,
+                size,
+                max_width,
+                outline,
+            } => {
+                let text_style = text_style(*size, *max_width, *outline, style, 1.0);
+                format!(
+                    "<g{alpha}>\n{}</g>\n",
+                    self.text(text, &text_style, position.x, position.y)
+                )
+            }
+            ShapeKind::Callout {
+                bounds,
+                target,
+                text,
+                size,
+            } => {
+                let b = bounds.normalized();
+                let padding = size * CALLOUT_PADDING;
+                let outline = Outline {
+                    fills: vec![callout(b, *target, padding)],
+                    holes: Vec::new(),
+                };
+                let background = paint("fill", style.fill.unwrap_or(Color::rgb(255, 255, 255)));
+                let text_style = callout_text_style(*size, b.width, style);
+                format!(
+                    "<g{alpha}>\n<path d=\"{}\" {background} {stroke} stroke-linejoin=\"round\"/>\n{}</g>\n",
+                    path_data(&outline),
+                    self.text(text, &text_style, b.x + padding, b.y + padding),
+                )
+            }
+            ShapeKind::Counter {
+                center,
+                number,
+                radius,
+            } => {
+                let background = paint("fill", style.fill.unwrap_or(style.stroke));
+                format!(
+                    r##"<g{alpha}>
+<circle cx="{x}" cy="{y}" r="{r}" {background}/>
+<text x="{x}" y="{y}" font-family="{family}" font-size="{size}" fill="#FFFFFF" text-anchor="middle" dominant-baseline="central">{number}</text>
+</g>
+"##,
+                    x = num(center.x),
+                    y = num(center.y),
+                    r = num(*radius),
+                    family = self.font_family(),
+                    size = num(radius * 1.2),
+                )
+            }
+            // 在 export 中处理
+            ShapeKind::Spotlight { .. } => String::new(),
+        }
+    }
+
+    /// 聚光灯：区域外为白色的遮罩，羽化用高斯模糊
+    fn spotlight_mask(
+        &self,
+        defs: &mut String,
+        id: usize,
+        areas: &[SpotlightArea],
+        feather: f32,
+        (width, height): (u32, u32),
+    ) {
+        let filter = if feather > 0.0 {
+            let _ = writeln!(
+                defs,
+                r#"<filter id="feather-{id}" filterUnits="userSpaceOnUse" x="0" y="0" width="{width}" height="{height}"><feGaussianBlur stdDeviation="{}"/></filter>"#,
+                num(feather / 3.0)
+            );
+            format!(r#" filter="url(#feather-{id})""#)
+        } else {
+            String::new()
+        };
+
+        let _ = writeln!(
+            defs,
+            r#"<mask id="spotlight-{id}" maskUnits="userSpaceOnUse" x="0" y="0" width="{width}" height="{height}">"#
+        );
+        let _ = writeln!(
+            defs,
+            r##"<rect width="{width}" height="{height}" fill="#FFFFFF"/>"##
+        );
+        let _ = writeln!(defs, "<g{filter}>");
+        for area in areas {
+            let b = area.bounds().normalized();
+            let _ = match area {
+                SpotlightArea::Rectangle(_) => writeln!(
+                    defs,
+                    r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#000000"/>"##,
+                    num(b.x),
+                    num(b.y),
+                    num(b.width),
+                    num(b.height)
+                ),
+                SpotlightArea::Ellipse(_) => writeln!(
+                    defs,
+                    r##"<ellipse cx="{}" cy="{}" rx="{}" ry="{}" fill="#000000"/>"##,
+                    num(b.center().x),
+                    num(b.center().y),
+                    num(b.width / 2.0),
+                    num(b.height / 2.0)
+                ),
+            };
+        }
+        defs.push_str("</g>\n</mask>\n");
+    }
+
+    pub fn export(&self, document: &AnnotationDocument) -> Result<String> {
+        let (width, height) = (document.width(), document.height());
+        let png = STANDARD.encode(document.base().to_png()?);
+
+        let mut defs = String::new();
+        let mut body = format!(
+            r#"<image width="{width}" height="{height}" preserveAspectRatio="none" xlink:href="data:image/png;base64,{png}"/>"#
+        ) + "\n";
+
+        for (id, shape) in document.shapes().iter().enumerate() {
+            let (areas, effect, feather) = match &shape.kind {
+                ShapeKind::Spotlight {
+                    areas,
+                    effect,
+                    feather,
+                } => (areas, effect, feather),
+                _ if shape.kind.is_rotatable() && shape.rotation != 0.0 => {
+                    let c = local_bounds(shape, &self.fonts).center();
+                    let _ = write!(
+                        body,
+                        "<g transform=\"rotate({} {} {})\">\n{}</g>\n",
+                        num(shape.rotation),
+                        num(c.x),
+                        num(c.y),
+                        self.shape(shape)
+                    );
+                    continue;
+                }
+                _ => {
+                    body.push_str(&self.shape(shape));
+                    continue;
+                }
+            };
+
+            self.spotlight_mask(&mut defs, id, areas, *feather, (width, height));
+            let strength = shape.style.opacity;
+            match effect {
+                SpotlightEffect::Dim { opacity } => {
+                    let _ = writeln!(
+                        body,
+                        r##"<rect width="{width}" height="{height}" fill="#000000" fill-opacity="{}" mask="url(#spotlight-{id})"/>"##,
+                        num((opacity * strength).clamp(0.0, 1.0))
+                    );
+                }
+                SpotlightEffect::Blur { radius } => {
+                    // 把下面的内容包成一组，再引用一份模糊后的副本叠在上面
+                    let _ = writeln!(
+                        defs,
+                        r#"<filter id="blur-{id}" filterUnits="userSpaceOnUse" x="0" y="0" width="{width}" height="{height}"><feGaussianBlur stdDeviation="{}" edgeMode="duplicate"/></filter>"#,
+                        num((radius / 3.0).max(0.5))
+                    );
+                    body = format!("<g id=\"layer-{id}\">\n{body}</g>\n");
+                    let _ = writeln!(
+                        body,
+                        r##"<use xlink:href="#layer-{id}" filter="url(#blur-{id})" mask="url(#spotlight-{id})"{}/>"##,
+                        opacity(strength)
+                    );
+                }
+            }
+        }
+
+        let mut svg = String::new();
+        svg.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+        );
+        if !defs.is_empty() {
+            let _ = write!(svg, "<defs>\n{defs}</defs>\n");
+        }
+        svg.push_str(&body);
+        svg.push_str("</svg>\n");
+        Ok(svg)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, document: &AnnotationDocument, path: P) -> Result<()> {
+        fs::write(path, self.export(document)?)?;
+        Ok(())
+    }
+}
+
+/// 使用内置字体导出 SVG
+pub fn to_svg(document: &AnnotationDocument) -> Result<String> {
+    SvgExporter::default().export(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::image::Image;
+    use crate::editor::annotation::{Bounds, Style};
+
+    #[test]
+    fn exports_vector_elements() {
+        let mut document = AnnotationDocument::new(Image::new(40, 30, vec![255; 40 * 30 * 4]));
+        document.add(
+            ShapeKind::Rectangle {
+                bounds: Bounds::new(30.0, 20.0, -20.0, -10.0),
+            },
+            Style {
+                opacity: 0.5,
+                ..Style::default()
+            },
+        );
+        document.add(
+            ShapeKind::Text {
+                position: Point::new(2.0, 2.0),
+                text: "a < b & c".to_string(),
+                size: 12.0,
+                max_width: None,
+                outline: None,
+            },
+            Style::default(),
+        );
+        let svg = to_svg(&document).unwrap();
+
+        assert!(svg.contains(r#"width="40" height="30" viewBox="0 0 40 30""#));
+        assert!(svg.contains("data:image/png;base64,iVBORw0KGgo"));
+        assert!(svg.contains(
+            r##"<rect x="10" y="10" width="20" height="10" fill="none" stroke="#FF3B30" stroke-width="4" opacity="0.5"/>"##
+        ));
+        assert!(svg.contains(">a &lt; b &amp; c</tspan>"));
+        assert!(svg.contains("font-family=\"'DejaVu Sans', 'Unifont CJK Subset', sans-serif\""));
+    }
+
+    #[test]
+    fn spotlight_blur_references_layers_below() {
+        let mut document = AnnotationDocument::new(Image::new(10, 10, vec![255; 400]));
+        document.add(
+            ShapeKind::Spotlight {
+                areas: vec![SpotlightArea::Ellipse(Bounds::new(2.0, 2.0, 6.0, 6.0))],
+                effect: SpotlightEffect::Blur { radius: 3.0 },
+                feather: 0.0,
+            },
+            Style::default(),
+        );
+        let svg = to_svg(&document).unwrap();
+
+        let layer = svg.find(r#"<g id="layer-0">"#).unwrap();
+        let image = svg.find("<image").unwrap();
+        let reference = svg.find(r##"<use xlink:href="#layer-0""##).unwrap();
+        assert!(layer < image && image < reference);
+        assert!(svg.contains(r#"<mask id="spotlight-0""#));
+        assert!(!svg.contains("feather-0"));
+        assert_eq!(num(-0.001), "0");
+        assert_eq!(num(1.5), "1.5");
+    }
+}
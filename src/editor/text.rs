@@ -0,0 +1,651 @@
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+
+use ab_glyph::{point, Font, FontArc, FontRef, FontVec, GlyphId, PxScale};
+use anyhow::{anyhow, Result};
+
+use crate::core::image::{BlendMode, Color, Image};
+use crate::editor::annotation::{Bounds, Point};
+use crate::editor::geometry::rounded_rect;
+use crate::editor::raster::Rasterizer;
+
+/// 内置字体，测试和无头环境都不依赖系统字体
+const BUNDLED: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+
+/// 内置的 CJK 后备字体，由 GNU Unifont 点阵转换，覆盖 GB2312 全部字符
+const BUNDLED_CJK: &[u8] = include_bytes!("../../assets/fonts/UnifontCJKSubset.ttf");
+
+#[derive(Clone)]
+enum FontData {
+    Static(&'static [u8]),
+    Owned(Arc<FontVec>),
+}
+
+impl FontData {
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            FontData::Static(data) => data,
+            FontData::Owned(font) => font.as_slice(),
+        }
+    }
+}
+
+/// 一个字体文件，rustybuzz 负责排版，ab_glyph 负责轮廓，两者共用同一份字节
+#[derive(Clone)]
+struct Face {
+    data: FontData,
+    index: u32,
+    glyphs: FontArc,
+}
+
+impl Face {
+    fn new(data: FontData, index: u32) -> Result<Face> {
+        let glyphs = match &data {
+            FontData::Static(data) => FontArc::new(FontRef::try_from_slice_and_index(data, index)?),
+            FontData::Owned(font) => FontArc::from(font.clone() as Arc<dyn Font + Send + Sync>),
+        };
+
+        // 提前检查 rustybuzz 能否解析
+        rustybuzz::Face::from_slice(data.as_slice(), index)
+            .ok_or_else(|| anyhow!("Unsupported font face {index}"))?;
+
+        Ok(Face {
+            data,
+            index,
+            glyphs,
+        })
+    }
+
+    /// 从 TTF/OTF 数据创建，字节直接交给 FontVec，不再复制
+    fn owned(data: Vec<u8>, index: u32) -> Result<Face> {
+        let font = FontVec::try_from_vec_and_index(data, index)?;
+        Face::new(FontData::Owned(Arc::new(font)), index)
+    }
+
+    /// 解析给 rustybuzz 用的字体，借用 data 中的字节
+    fn shaper(&self) -> Option<rustybuzz::Face<'_>> {
+        rustybuzz::Face::from_slice(self.data.as_slice(), self.index)
+    }
+
+    fn has_glyph(&self, c: char) -> bool {
+        self.glyphs.glyph_id(c).0 != 0
+    }
+
+    /// 字号为 size 时每个字体单位对应的像素数，与 ab_glyph 的 PxScale 保持一致
+    fn px_per_unit(&self, size: f32) -> f32 {
+        size / self.glyphs.height_unscaled()
+    }
+}
+
+/// 主字体加若干后备字体，主字体缺字时依次在后备字体中查找
+#[derive(Clone)]
+pub struct FontSet {
+    faces: Vec<Face>,
+}
+
+impl Default for FontSet {
+    fn default() -> Self {
+        FontSet::bundled()
+    }
+}
+
+impl FontSet {
+    /// 内置的 DejaVu Sans，缺字时用内置的 CJK 字体，不读取任何系统字体
+    pub fn bundled() -> FontSet {
+        let faces = [BUNDLED, BUNDLED_CJK]
+            .into_iter()
+            .map(|data| Face::new(FontData::Static(data), 0).expect("Bundled font is valid"))
+            .collect();
+        FontSet { faces }
+    }
+
+    /// 从 TTF/OTF 数据创建，index 为字体集合（TTC）中的序号
+    pub fn from_bytes(data: Vec<u8>, index: u32) -> Result<FontSet> {
+        let face = Face::owned(data, index)?;
+        Ok(FontSet { faces: vec![face] })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<FontSet> {
+        FontSet::from_bytes(fs::read(path)?, 0)
+    }
+
+    /// 添加后备字体，排在已有字体之后，例如内置 CJK 字体缺字时再用系统字体
+    pub fn push_fallback(&mut self, data: Vec<u8>, index: u32) -> Result<()> {
+        self.faces.push(Face::owned(data, index)?);
+        Ok(())
+    }
+
+    /// 从文件添加后备字体，例如系统里的 Noto Sans CJK 或微软雅黑
+    pub fn push_fallback_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+        self.push_fallback(fs::read(path)?, 0)
+    }
+
+    /// 各字体的家族名，按后备顺序排列，SVG 导出时用作 font-family
+    pub fn family_names(&self) -> Vec<String> {
+        self.faces
+            .iter()
+            .filter_map(|face| {
+                let name = face.shaper()?.names().into_iter().find(|name| {
+                    name.name_id == rustybuzz::ttf_parser::name_id::FAMILY && name.is_unicode()
+                })?;
+                // Unicode 名称为 UTF-16BE
+                let units: Vec<u16> = name
+                    .name
+                    .chunks_exact(2)
+                    .map(|c| u16::from_be_bytes([c[0], c[1]]))
+                    .collect();
+                String::from_utf16(&units).ok()
+            })
+            .collect()
+    }
+
+    fn face_for(&self, c: char) -> usize {
+        self.faces.iter().position(|f| f.has_glyph(c)).unwrap_or(0)
+    }
+}
+
+/// 多行文字的对齐方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// 文字描边
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextOutline {
+    pub color: Color,
+    pub width: f32,
+}
+
+/// 文字背景框
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextBackground {
+    pub color: Color,
+    pub padding: f32,
+    pub radius: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStyle {
+    /// 字号，即一行文字从上伸部到下伸部的像素高度
+    pub size: f32,
+    pub color: Color,
+    /// 超过这个宽度时自动换行
+    pub max_width: Option<f32>,
+    /// 行距倍数
+    pub line_spacing: f32,
+    pub align: Align,
+    pub outline: Option<TextOutline>,
+    pub background: Option<TextBackground>,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle {
+            size: 16.0,
+            color: Color::rgb(0, 0, 0),
+            max_width: None,
+            line_spacing: 1.0,
+            align: Align::Left,
+            outline: None,
+            background: None,
+        }
+    }
+}
+
+/// 排版后的字形
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PositionedGlyph {
+    /// FontSet 中字体的序号
+    pub face: usize,
+    pub id: u16,
+    /// 基线上的原点
+    pub x: f32,
+    pub y: f32,
+}
+
+/// 排版后的一行
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    /// 这一行在原文中的字节范围，不含换行符和行尾空白
+    pub range: Range<usize>,
+    /// 行首的 x 和基线的 y
+    pub x: f32,
+    pub baseline: f32,
+    pub width: f32,
+}
+
+/// 排版结果，坐标以文字块左上角为原点
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLayout {
+    pub glyphs: Vec<PositionedGlyph>,
+    pub lines: Vec<TextLine>,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// 渲染结果，origin 是文字块左上角在 image 中的位置（描边、背景框会向外扩展）
+pub struct TextBlock {
+    pub image: Image,
+    pub origin: Point,
+}
+
+/// 一个段落里经过 shaping 的字形，cluster 为字形对应文字在段落中的字节偏移
+#[derive(Debug, Clone, Copy)]
+struct ShapedGlyph {
+    face: usize,
+    id: u16,
+    cluster: usize,
+    advance: f32,
+    offset_x: f32,
+    offset_y: f32,
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x2E80..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF | 0x20000..=0x2FA1F)
+}
+
+/// 不能出现在行首的标点
+fn is_closing_punctuation(c: char) -> bool {
+    "，。、！？：；）》」』】〕,.!?:;)]}".contains(c)
+}
+
+/// 可以在其后换行的最小单元，每个单元包含尾部空白
+fn break_units(text: &str) -> Vec<Range<usize>> {
+    let mut units: Vec<Range<usize>> = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<char> = None;
+
+    for (i, c) in text.char_indices() {
+        if let Some(p) = previous {
+            let after_space = p.is_whitespace() && !c.is_whitespace();
+            let around_cjk = (is_cjk(p) || is_cjk(c)) && !c.is_whitespace();
+            if (after_space || around_cjk) && !is_closing_punctuation(c) {
+                units.push(start..i);
+                start = i;
+            }
+        }
+        previous = Some(c);
+    }
+
+    if start < text.len() {
+        units.push(start..text.len());
+    }
+    units
+}
+
+impl FontSet {
+    /// 按字体切分后逐段 shaping，shapers 与 faces 一一对应
+    fn shape(
+        &self,
+        shapers: &[Option<rustybuzz::Face>],
+        text: &str,
+        size: f32,
+    ) -> Vec<ShapedGlyph> {
+        // 空白和组合字符跟随前一个字符的字体
+        let mut runs: Vec<(usize, Range<usize>)> = Vec::new();
+        for (i, c) in text.char_indices() {
+            let inherit = c.is_whitespace() || ('\u{0300}'..='\u{036F}').contains(&c);
+            let face = match runs.last() {
+                Some((face, _)) if inherit => *face,
+                _ => self.face_for(c),
+            };
+
+            match runs.last_mut() {
+                Some((last, range)) if *last == face => range.end = i + c.len_utf8(),
+                _ => runs.push((face, i..i + c.len_utf8())),
+            }
+        }
+
+        let mut glyphs = Vec::new();
+        for (face_index, range) in runs {
+            let face = &self.faces[face_index];
+            let shaper = match &shapers[face_index] {
+                Some(shaper) => shaper,
+                None => continue,
+            };
+            let scale = face.px_per_unit(size);
+
+            let mut buffer = rustybuzz::UnicodeBuffer::new();
+            buffer.push_str(&text[range.clone()]);
+            buffer.guess_segment_properties();
+            let shaped = rustybuzz::shape(shaper, &[], buffer);
+
+            for (info, position) in shaped.glyph_infos().iter().zip(shaped.glyph_positions()) {
+                glyphs.push(ShapedGlyph {
+                    face: face_index,
+                    id: info.glyph_id as u16,
+                    cluster: range.start + info.cluster as usize,
+                    advance: position.x_advance as f32 * scale,
+                    offset_x: position.x_offset as f32 * scale,
+                    offset_y: position.y_offset as f32 * scale,
+                });
+            }
+        }
+
+        glyphs
+    }
+
+    /// 排版：按 `\n` 分段，超过 max_width 时在空格或 CJK 字符之间换行
+    pub fn layout(&self, text: &str, style: &TextStyle) -> TextLayout {
+        let primary = &self.faces[0];
+        let scale = primary.px_per_unit(style.size);
+        let ascent = primary.glyphs.ascent_unscaled() * scale;
+        let descent = primary.glyphs.descent_unscaled() * scale;
+        let line_gap = primary.glyphs.line_gap_unscaled() * scale;
+        let line_height = (ascent - descent + line_gap) * style.line_spacing;
+
+        // 每次排版只解析一遍 rustybuzz 字体，各段共用
+        let shapers: Vec<_> = self.faces.iter().map(Face::shaper).collect();
+
+        // 每行的字形、原文范围和宽度
+        let mut lines: Vec<(Vec<ShapedGlyph>, Range<usize>, f32)> = Vec::new();
+        let mut offset = 0;
+        for paragraph in text.split('\n') {
+            let glyphs = self.shape(&shapers, paragraph, style.size);
+            let width_of = |range: &Range<usize>, trim: bool| -> f32 {
+                let text = &paragraph[range.clone()];
+                let end = if trim {
+                    range.start + text.trim_end().len()
+                } else {
+                    range.end
+                };
+                glyphs
+                    .iter()
+                    .filter(|g| g.cluster >= range.start && g.cluster < end)
+                    .map(|g| g.advance)
+                    .sum()
+            };
+
+            // 贪心断行，得到每行的字节范围
+            let mut ranges: Vec<Range<usize>> = Vec::new();
+            let mut current: Option<Range<usize>> = None;
+            for unit in break_units(paragraph) {
+                let candidate = match &current {
+                    Some(range) => range.start..unit.end,
+                    None => unit.clone(),
+                };
+
+                let fits = match style.max_width {
+                    Some(max) => width_of(&candidate, true) <= max,
+                    None => true,
+                };
+                if fits || current.is_none() {
+                    current = Some(candidate);
+                } else {
+                    ranges.extend(current.take());
+                    current = Some(unit);
+                }
+            }
+            ranges.push(current.unwrap_or(0..0));
+
+            // 单个单元仍然超宽时按字符断开
+            if let Some(max) = style.max_width {
+                ranges = ranges
+                    .into_iter()
+                    .flat_map(|range| {
+                        if width_of(&range, true) <= max {
+                            return vec![range];
+                        }
+
+                        let mut pieces = Vec::new();
+                        let mut start = range.start;
+                        for (i, c) in paragraph[range.clone()].char_indices() {
+                            let end = range.start + i + c.len_utf8();
+                            if end > start + c.len_utf8() && width_of(&(start..end), true) > max {
+                                pieces.push(start..range.start + i);
+                                start = range.start + i;
+                            }
+                        }
+                        pieces.push(start..range.end);
+                        pieces
+                    })
+                    .collect();
+            }
+
+            for range in ranges {
+                let trimmed_end = range.start + paragraph[range.clone()].trim_end().len();
+                let line: Vec<ShapedGlyph> = glyphs
+                    .iter()
+                    .filter(|g| g.cluster >= range.start && g.cluster < range.end)
+                    .cloned()
+                    .collect();
+                let width = width_of(&(range.start..trimmed_end), false);
+                lines.push((line, offset + range.start..offset + trimmed_end, width));
+            }
+            offset += paragraph.len() + 1;
+        }
+
+        let width = lines.iter().map(|(_, _, w)| *w).fold(0.0, f32::max);
+        let mut glyphs = Vec::new();
+        let mut text_lines = Vec::new();
+        for (row, (line, range, line_width)) in lines.iter().enumerate() {
+            let baseline = ascent + row as f32 * line_height;
+            let mut x = match style.align {
+                Align::Left => 0.0,
+                Align::Center => (width - line_width) / 2.0,
+                Align::Right => width - line_width,
+            };
+            text_lines.push(TextLine {
+                range: range.clone(),
+                x,
+                baseline,
+                width: *line_width,
+            });
+
+            for g in line {
+                glyphs.push(PositionedGlyph {
+                    face: g.face,
+                    id: g.id,
+                    x: x + g.offset_x,
+                    y: baseline - g.offset_y,
+                });
+                x += g.advance;
+            }
+        }
+
+        let height = match lines.len() {
+            0 => 0.0,
+            n => (n - 1) as f32 * line_height + ascent - descent,
+        };
+
+        TextLayout {
+            glyphs,
+            lines: text_lines,
+            width,
+            height,
+        }
+    }
+
+    /// 排版并渲染成透明背景的图
+    pub fn render(&self, text: &str, style: &TextStyle) -> TextBlock {
+        let layout = self.layout(text, style);
+        let outline = style.outline.map(|o| o.width.max(0.0)).unwrap_or(0.0);
+        let padding = style.background.map(|b| b.padding.max(0.0)).unwrap_or(0.0);
+        let inset = (outline + padding).ceil();
+
+        let width = (layout.width + inset * 2.0).ceil() as u32;
+        let height = (layout.height + inset * 2.0).ceil() as u32;
+        let mut image = Image::new(width, height, vec![0; width as usize * height as usize * 4]);
+
+        // 字形覆盖率
+        let mut coverage = vec![0f32; width as usize * height as usize];
+        for glyph in &layout.glyphs {
+            let face = &self.faces[glyph.face];
+            let positioned = GlyphId(glyph.id).with_scale_and_position(
+                PxScale::from(style.size),
+                point(glyph.x + inset, glyph.y + inset),
+            );
+
+            if let Some(outlined) = face.glyphs.outline_glyph(positioned) {
+                let bounds = outlined.px_bounds();
+                outlined.draw(|x, y, c| {
+                    let px = bounds.min.x as i32 + x as i32;
+                    let py = bounds.min.y as i32 + y as i32;
+                    if px >= 0 && py >= 0 && (px as u32) < width && (py as u32) < height {
+                        let i = py as usize * width as usize + px as usize;
+                        coverage[i] = (coverage[i] + c).min(1.0);
+                    }
+                });
+            }
+        }
+
+        if let Some(background) = style.background {
+            let bounds = Bounds::new(
+                outline,
+                outline,
+                width as f32 - outline * 2.0,
+                height as f32 - outline * 2.0,
+            );
+            let mut rasterizer = Rasterizer::new(0, 0, width, height);
+            rasterizer.fill(&rounded_rect(bounds, background.radius));
+            paint(&mut image, &rasterizer.coverage(), background.color);
+        }
+
+        if let Some(text_outline) = style.outline {
+            paint(
+                &mut image,
+                &dilate(&coverage, width, height, text_outline.width),
+                text_outline.color,
+            );
+        }
+
+        paint(&mut image, &coverage, style.color);
+
+        TextBlock {
+            image,
+            origin: Point::new(inset, inset),
+        }
+    }
+}
+
+/// 覆盖率向外扩张 radius 像素，用于文字描边
+fn dilate(coverage: &[f32], width: u32, height: u32, radius: f32) -> Vec<f32> {
+    let (w, h) = (width as i32, height as i32);
+    let r = radius.ceil() as i32;
+    let mut result = coverage.to_vec();
+
+    for y in 0..h {
+        for x in 0..w {
+            let mut value = 0f32;
+            for dy in -r..=r {
+                for dx in -r..=r {
+                    let (sx, sy) = (x + dx, y + dy);
+                    if sx < 0 || sy < 0 || sx >= w || sy >= h {
+                        continue;
+                    }
+                    let distance = ((dx * dx + dy * dy) as f32).sqrt();
+                    let weight = (radius + 0.5 - distance).clamp(0.0, 1.0);
+                    value = value.max(coverage[(sy * w + sx) as usize] * weight);
+                }
+            }
+            result[(y * w + x) as usize] = value;
+        }
+    }
+
+    result
+}
+
+/// 按覆盖率把 color 叠加到 image 上
+fn paint(image: &mut Image, coverage: &[f32], color: Color) {
+    let layer: Vec<u8> = coverage
+        .iter()
+        .flat_map(|c| {
+            [
+                color.r,
+                color.g,
+                color.b,
+                (color.a as f32 * c).round() as u8,
+            ]
+        })
+        .collect();
+    let layer = Image::new(image.width(), image.height(), layer);
+    image.blend(&layer.view(), 0, 0, BlendMode::Over, 1.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_at_spaces_and_between_cjk() {
+        assert_eq!(
+            break_units("hello world 截图工具，好用"),
+            vec![0..6, 6..12, 12..15, 15..18, 18..21, 21..27, 27..30, 30..33]
+        );
+
+        let fonts = FontSet::bundled();
+        let style = TextStyle {
+            size: 20.0,
+            max_width: Some(80.0),
+            ..TextStyle::default()
+        };
+        let single = fonts.layout("hello", &style);
+        let wrapped = fonts.layout("hello hello hello", &style);
+
+        assert_eq!(single.lines.len(), 1);
+        assert_eq!(wrapped.lines.len(), 3);
+        assert_eq!(wrapped.lines[2].range, 12..17);
+        assert!(wrapped.width <= 80.0);
+        assert_eq!(fonts.layout("a\nb", &TextStyle::default()).lines.len(), 2);
+        assert_eq!(
+            fonts.family_names(),
+            vec!["DejaVu Sans".to_string(), "Unifont CJK Subset".to_string()]
+        );
+    }
+
+    #[test]
+    fn cjk_glyphs_are_covered_and_produce_ink() {
+        let fonts = FontSet::bundled();
+        let style = TextStyle {
+            size: 24.0,
+            color: Color::rgb(255, 255, 255),
+            background: Some(TextBackground {
+                color: Color::rgb(255, 204, 0),
+                padding: 4.0,
+                radius: 4.0,
+            }),
+            ..TextStyle::default()
+        };
+
+        let latin = fonts.render("Hello", &style);
+        assert_eq!(latin.origin, Point::new(4.0, 4.0));
+        // 内边距处是背景色，左上角圆角外透明
+        assert_eq!(
+            latin
+                .image
+                .pixel(latin.image.width() - 2, latin.image.height() / 2),
+            Some(Color::rgb(255, 204, 0))
+        );
+        assert_eq!(latin.image.pixel(0, 0), Some(Color::new(0, 0, 0, 0)));
+
+        // DejaVu 没有汉字，全部落到内置的 CJK 字体上
+        for c in "世界截图，".chars() {
+            assert_eq!(fonts.face_for(c), 1, "{c} is not covered");
+            assert!(fonts.faces[1].has_glyph(c));
+        }
+
+        // 覆盖之外还要真的画出轮廓，不能是空白字形
+        let plain = TextStyle {
+            size: 24.0,
+            color: Color::rgb(255, 255, 255),
+            ..TextStyle::default()
+        };
+        for c in "世界截".chars() {
+            let block = fonts.render(&c.to_string(), &plain);
+            let ink = block
+                .image
+                .rgba()
+                .chunks_exact(4)
+                .filter(|p| p[3] > 0)
+                .count();
+            assert!(ink > 60, "{c} has only {ink} inked pixels");
+        }
+    }
+}
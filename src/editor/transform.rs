@@ -0,0 +1,696 @@
+use crate::editor::annotation::{
+    AnnotationDocument, Bounds, Point, Shape, ShapeId, ShapeKind, SpotlightArea,
+};
+use crate::editor::geometry::{
+    arrow, callout, distance, distance_to_polyline, ellipse, quadratic, rectangle, rotate_point,
+    Outline,
+};
+use crate::editor::render::{text_style, CALLOUT_PADDING};
+use crate::editor::text::FontSet;
+
+/// 旋转手柄到包围盒上边中点的距离
+pub const ROTATE_HANDLE_OFFSET: f32 = 24.0;
+
+/// 选中图形后显示的手柄
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Handle {
+    TopLeft,
+    Top,
+    TopRight,
+    Right,
+    BottomRight,
+    Bottom,
+    BottomLeft,
+    Left,
+    /// 旋转
+    Rotate,
+    /// 直线、箭头的起点和终点
+    Start,
+    End,
+    /// 箭头的弯曲控制点
+    Control,
+    /// 气泡尖角指向的位置
+    Target,
+}
+
+impl Handle {
+    /// 包围盒上的八个缩放手柄，顺时针排列
+    pub const BOX: [Handle; 8] = [
+        Handle::TopLeft,
+        Handle::Top,
+        Handle::TopRight,
+        Handle::Right,
+        Handle::BottomRight,
+        Handle::Bottom,
+        Handle::BottomLeft,
+        Handle::Left,
+    ];
+
+    /// 缩放手柄在包围盒上的相对位置
+    pub fn anchor(&self) -> Option<(f32, f32)> {
+        match self {
+            Handle::TopLeft => Some((0.0, 0.0)),
+            Handle::Top => Some((0.5, 0.0)),
+            Handle::TopRight => Some((1.0, 0.0)),
+            Handle::Right => Some((1.0, 0.5)),
+            Handle::BottomRight => Some((1.0, 1.0)),
+            Handle::Bottom => Some((0.5, 1.0)),
+            Handle::BottomLeft => Some((0.0, 1.0)),
+            Handle::Left => Some((0.0, 0.5)),
+            _ => None,
+        }
+    }
+}
+
+fn at(bounds: Bounds, (ax, ay): (f32, f32)) -> Point {
+    Point::new(bounds.x + bounds.width * ax, bounds.y + bounds.height * ay)
+}
+
+fn expand(bounds: Bounds, amount: f32) -> Bounds {
+    let b = bounds.normalized();
+    Bounds::new(
+        b.x - amount,
+        b.y - amount,
+        b.width + amount * 2.0,
+        b.height + amount * 2.0,
+    )
+}
+
+fn contains(bounds: Bounds, p: Point) -> bool {
+    let b = bounds.normalized();
+    p.x >= b.x && p.x <= b.x + b.width && p.y >= b.y && p.y <= b.y + b.height
+}
+
+fn points_bounds(points: impl IntoIterator<Item = Point>) -> Bounds {
+    let (mut left, mut top) = (f32::MAX, f32::MAX);
+    let (mut right, mut bottom) = (f32::MIN, f32::MIN);
+    for p in points {
+        left = left.min(p.x);
+        top = top.min(p.y);
+        right = right.max(p.x);
+        bottom = bottom.max(p.y);
+    }
+
+    if left > right {
+        return Bounds::default();
+    }
+    Bounds::new(left, top, right - left, bottom - top)
+}
+
+fn union(a: Bounds, b: Bounds) -> Bounds {
+    let corners = |b: Bounds| {
+        [
+            Point::new(b.x, b.y),
+            Point::new(b.x + b.width, b.y + b.height),
+        ]
+    };
+    points_bounds(
+        corners(a.normalized())
+            .into_iter()
+            .chain(corners(b.normalized())),
+    )
+}
+
+/// 角度归一化到 (-180, 180]
+pub fn normalize_angle(degrees: f32) -> f32 {
+    let d = degrees.rem_euclid(360.0);
+    if d > 180.0 {
+        d - 360.0
+    } else {
+        d
+    }
+}
+
+/// 从 center 指向 point 的方向，正上方为 0，顺时针为正
+pub fn angle_from(center: Point, point: Point) -> f32 {
+    normalize_angle(libm::atan2f(point.x - center.x, center.y - point.y).to_degrees())
+}
+
+/// 八个缩放手柄的位置
+pub fn box_handles(bounds: Bounds) -> Vec<(Handle, Point)> {
+    let b = bounds.normalized();
+    Handle::BOX
+        .iter()
+        .filter_map(|h| Some((*h, at(b, h.anchor()?))))
+        .collect()
+}
+
+/// 拖动缩放手柄到 to，对边（对角）保持不动，拖过对边时翻转
+///
+/// aspect 为宽高比，拖角时按变化大的方向保持比例，拖边时另一方向以中心为基准缩放
+pub fn drag_box_handle(bounds: Bounds, handle: Handle, to: Point, aspect: Option<f32>) -> Bounds {
+    let b = bounds.normalized();
+    let (ax, ay) = match handle.anchor() {
+        Some(anchor) => anchor,
+        None => return b,
+    };
+
+    let (mut x0, mut x1) = (b.x, b.x + b.width);
+    let (mut y0, mut y1) = (b.y, b.y + b.height);
+    if ax != 0.5 {
+        x0 = if ax == 0.0 { b.x + b.width } else { b.x };
+        x1 = to.x;
+    }
+    if ay != 0.5 {
+        y0 = if ay == 0.0 { b.y + b.height } else { b.y };
+        y1 = to.y;
+    }
+
+    if let Some(aspect) = aspect.filter(|a| *a > 0.0) {
+        let (width, height) = ((x1 - x0).abs(), (y1 - y0).abs());
+        let center = b.center();
+        if ax == 0.5 {
+            let width = height * aspect;
+            (x0, x1) = (center.x - width / 2.0, center.x + width / 2.0);
+        } else if ay == 0.5 {
+            let height = width / aspect;
+            (y0, y1) = (center.y - height / 2.0, center.y + height / 2.0);
+        } else if width > height * aspect {
+            y1 = y0 + (y1 - y0).signum() * width / aspect;
+        } else {
+            x1 = x0 + (x1 - x0).signum() * height * aspect;
+        }
+    }
+
+    Bounds::from_points(Point::new(x0, y0), Point::new(x1, y1)).normalized()
+}
+
+/// 文字块在本地坐标中的范围，含背景框
+fn text_bounds(shape: &Shape, fonts: &FontSet) -> Bounds {
+    match &shape.kind {
+        ShapeKind::Text {
+            position,
+            text,
+            size,
+            max_width,
+            outline,
+        } => {
+            let style = text_style(*size, *max_width, *outline, &shape.style, 1.0);
+            let layout = fonts.layout(text, &style);
+            let bounds = Bounds::new(position.x, position.y, layout.width, layout.height);
+            match style.background {
+                Some(background) => expand(bounds, background.padding),
+                None => bounds,
+            }
+        }
+        _ => Bounds::default(),
+    }
+}
+
+/// 本地包围盒：未旋转、不含线宽，缩放手柄画在它上面
+pub fn local_bounds(shape: &Shape, fonts: &FontSet) -> Bounds {
+    match &shape.kind {
+        ShapeKind::Rectangle { bounds }
+        | ShapeKind::Ellipse { bounds }
+        | ShapeKind::Callout { bounds, .. } => bounds.normalized(),
+        ShapeKind::Line { from, to } => points_bounds([*from, *to]),
+        ShapeKind::Arrow {
+            from, to, control, ..
+        } => match control {
+            Some(control) => points_bounds(quadratic(*from, *control, *to)),
+            None => points_bounds([*from, *to]),
+        },
+        ShapeKind::Freehand { points } | ShapeKind::Highlighter { points } => {
+            points_bounds(points.iter().copied())
+        }
+        ShapeKind::Text { .. } => text_bounds(shape, fonts),
+        ShapeKind::Counter { center, radius, .. } => Bounds::new(
+            center.x - radius,
+            center.y - radius,
+            radius * 2.0,
+            radius * 2.0,
+        ),
+        ShapeKind::Spotlight { areas, .. } => areas
+            .iter()
+            .map(|area| area.bounds().normalized())
+            .reduce(union)
+            .unwrap_or_default(),
+    }
+}
+
+/// 旋转中心和角度，不旋转的图形角度为 0
+fn frame(shape: &Shape, fonts: &FontSet) -> (Point, f32) {
+    if shape.kind.is_rotatable() && shape.rotation != 0.0 {
+        (local_bounds(shape, fonts).center(), shape.rotation)
+    } else {
+        (Point::default(), 0.0)
+    }
+}
+
+/// 画布上的轴对齐包围盒，含线宽、箭头和旋转
+pub fn bounds(shape: &Shape, fonts: &FontSet) -> Bounds {
+    let width = shape.style.width;
+    let local = match &shape.kind {
+        ShapeKind::Arrow {
+            from,
+            to,
+            control,
+            double,
+            tapered,
+        } => {
+            let outline = arrow(*from, *to, *control, width, *double, *tapered);
+            return points_bounds(outline.fills.into_iter().flatten());
+        }
+        ShapeKind::Text { .. } | ShapeKind::Counter { .. } | ShapeKind::Spotlight { .. } => {
+            local_bounds(shape, fonts)
+        }
+        _ => expand(local_bounds(shape, fonts), width / 2.0),
+    };
+
+    let (pivot, rotation) = frame(shape, fonts);
+    let mut corners = vec![
+        Point::new(local.x, local.y),
+        Point::new(local.x + local.width, local.y),
+        Point::new(local.x + local.width, local.y + local.height),
+        Point::new(local.x, local.y + local.height),
+    ];
+    if let ShapeKind::Callout { target, .. } = &shape.kind {
+        corners.push(*target);
+    }
+    points_bounds(
+        corners
+            .into_iter()
+            .map(|p| rotate_point(p, pivot, rotation)),
+    )
+}
+
+/// 多个图形的整体包围盒
+pub fn selection_bounds(
+    document: &AnnotationDocument,
+    ids: &[ShapeId],
+    fonts: &FontSet,
+) -> Option<Bounds> {
+    ids.iter()
+        .filter_map(|id| document.get(*id))
+        .map(|shape| bounds(shape, fonts))
+        .reduce(union)
+}
+
+/// 点是否落在图形上，tolerance 为允许的误差，描边图形只有点在线附近才算命中
+pub fn hit_test(shape: &Shape, point: Point, tolerance: f32, fonts: &FontSet) -> bool {
+    let (pivot, rotation) = frame(shape, fonts);
+    let p = rotate_point(point, pivot, -rotation);
+    let half = shape.style.width / 2.0;
+    let reach = half + tolerance;
+
+    match &shape.kind {
+        ShapeKind::Rectangle { bounds } => {
+            let b = bounds.normalized();
+            let inner = expand(b, -reach);
+            contains(expand(b, reach), p)
+                && (shape.style.fill.is_some()
+                    || inner.width <= 0.0
+                    || inner.height <= 0.0
+                    || !contains(inner, p))
+        }
+        ShapeKind::Ellipse { bounds } => {
+            let b = bounds.normalized();
+            let (c, rx, ry) = (b.center(), b.width / 2.0, b.height / 2.0);
+            let inside = |rx: f32, ry: f32| {
+                rx > 0.0
+                    && ry > 0.0
+                    && ((p.x - c.x) / rx).powi(2) + ((p.y - c.y) / ry).powi(2) <= 1.0
+            };
+            inside(rx + reach, ry + reach)
+                && (shape.style.fill.is_some() || !inside(rx - reach, ry - reach))
+        }
+        ShapeKind::Line { from, to } => distance_to_polyline(&[*from, *to], p) <= reach,
+        ShapeKind::Arrow {
+            from,
+            to,
+            control,
+            double,
+            tapered,
+        } => {
+            let outline = arrow(*from, *to, *control, shape.style.width, *double, *tapered);
+            outline.contains(p) || outline.distance_to_edges(p) <= tolerance
+        }
+        ShapeKind::Freehand { points } | ShapeKind::Highlighter { points } => {
+            distance_to_polyline(points, p) <= reach
+        }
+        ShapeKind::Text { .. } => contains(expand(text_bounds(shape, fonts), tolerance), p),
+        ShapeKind::Callout {
+            bounds,
+            target,
+            size,
+            ..
+        } => {
+            let outline = Outline {
+                fills: vec![callout(*bounds, *target, size * CALLOUT_PADDING)],
+                holes: Vec::new(),
+            };
+            outline.contains(p) || outline.distance_to_edges(p) <= reach
+        }
+        ShapeKind::Counter { center, radius, .. } => distance(*center, p) <= radius + tolerance,
+        // 聚光灯覆盖整张图，只有点在区域边缘时才算命中，避免挡住下面的图形
+        ShapeKind::Spotlight { areas, .. } => areas.iter().any(|area| {
+            let b = area.bounds().normalized();
+            let contour = match area {
+                SpotlightArea::Rectangle(_) => rectangle(b),
+                SpotlightArea::Ellipse(_) => ellipse(b.center(), b.width / 2.0, b.height / 2.0),
+            };
+            let outline = Outline {
+                fills: vec![contour],
+                holes: Vec::new(),
+            };
+            outline.distance_to_edges(p) <= tolerance
+        }),
+    }
+}
+
+/// 点中的最上层图形
+pub fn pick(
+    document: &AnnotationDocument,
+    point: Point,
+    tolerance: f32,
+    fonts: &FontSet,
+) -> Option<ShapeId> {
+    document
+        .shapes()
+        .iter()
+        .rev()
+        .find(|shape| hit_test(shape, point, tolerance, fonts))
+        .map(|shape| shape.id)
+}
+
+/// 框选：包围盒完全落在 area 内的图形，按绘制顺序排列
+pub fn marquee(document: &AnnotationDocument, area: Bounds, fonts: &FontSet) -> Vec<ShapeId> {
+    let area = area.normalized();
+    document
+        .shapes()
+        .iter()
+        .filter(|shape| {
+            let b = bounds(shape, fonts);
+            contains(area, Point::new(b.x, b.y))
+                && contains(area, Point::new(b.x + b.width, b.y + b.height))
+        })
+        .map(|shape| shape.id)
+        .collect()
+}
+
+/// 图形的手柄和它们在画布上的位置
+pub fn handles(shape: &Shape, fonts: &FontSet) -> Vec<(Handle, Point)> {
+    let (pivot, rotation) = frame(shape, fonts);
+    let rotate = |handles: Vec<(Handle, Point)>| -> Vec<(Handle, Point)> {
+        handles
+            .into_iter()
+            .map(|(h, p)| (h, rotate_point(p, pivot, rotation)))
+            .collect()
+    };
+
+    match &shape.kind {
+        ShapeKind::Line { from, to } => vec![(Handle::Start, *from), (Handle::End, *to)],
+        ShapeKind::Arrow {
+            from, to, control, ..
+        } => {
+            // 直箭头的控制点在中点，拖动后变成曲线
+            let control =
+                control.unwrap_or(Point::new((from.x + to.x) / 2.0, (from.y + to.y) / 2.0));
+            vec![
+                (Handle::Start, *from),
+                (Handle::End, *to),
+                (Handle::Control, control),
+            ]
+        }
+        ShapeKind::Freehand { .. } | ShapeKind::Highlighter { .. } => {
+            box_handles(local_bounds(shape, fonts))
+        }
+        ShapeKind::Counter { .. } | ShapeKind::Spotlight { .. } => Vec::new(),
+        kind => {
+            let b = local_bounds(shape, fonts);
+            let mut handles = box_handles(b);
+            handles.push((
+                Handle::Rotate,
+                Point::new(b.x + b.width / 2.0, b.y - ROTATE_HANDLE_OFFSET),
+            ));
+            if let ShapeKind::Callout { target, .. } = kind {
+                handles.push((Handle::Target, *target));
+            }
+            rotate(handles)
+        }
+    }
+}
+
+/// 点中的手柄，多个手柄重叠时取列表中靠前的
+pub fn handle_at(shape: &Shape, point: Point, tolerance: f32, fonts: &FontSet) -> Option<Handle> {
+    handles(shape, fonts)
+        .into_iter()
+        .find(|(_, p)| distance(*p, point) <= tolerance)
+        .map(|(h, _)| h)
+}
+
+/// 把图形从 from 范围线性映射到 to 范围，线宽不变，文字字号按高度缩放
+pub fn scale(shape: &mut Shape, from: Bounds, to: Bounds) {
+    let (from, to) = (from.normalized(), to.normalized());
+    let sx = if from.width > 0.0 {
+        to.width / from.width
+    } else {
+        1.0
+    };
+    let sy = if from.height > 0.0 {
+        to.height / from.height
+    } else {
+        1.0
+    };
+    let map = |p: &mut Point| {
+        p.x = to.x + (p.x - from.x) * sx;
+        p.y = to.y + (p.y - from.y) * sy;
+    };
+    let map_bounds = |b: &mut Bounds| {
+        let mut corner = Point::new(b.x, b.y);
+        map(&mut corner);
+        *b = Bounds::new(corner.x, corner.y, b.width * sx, b.height * sy);
+    };
+
+    match &mut shape.kind {
+        ShapeKind::Rectangle { bounds } | ShapeKind::Ellipse { bounds } => map_bounds(bounds),
+        ShapeKind::Line { from, to } => {
+            map(from);
+            map(to);
+        }
+        ShapeKind::Arrow {
+            from, to, control, ..
+        } => {
+            map(from);
+            map(to);
+            control.iter_mut().for_each(map);
+        }
+        ShapeKind::Freehand { points } | ShapeKind::Highlighter { points } => {
+            points.iter_mut().for_each(map)
+        }
+        ShapeKind::Text {
+            position,
+            size,
+            max_width,
+            ..
+        } => {
+            map(position);
+            *size *= sy;
+            if let Some(width) = max_width {
+                *width *= sx;
+            }
+        }
+        ShapeKind::Callout { bounds, target, .. } => {
+            map_bounds(bounds);
+            map(target);
+        }
+        ShapeKind::Counter { center, .. } => map(center),
+        ShapeKind::Spotlight { areas, .. } => {
+            for area in areas {
+                match area {
+                    SpotlightArea::Rectangle(bounds) | SpotlightArea::Ellipse(bounds) => {
+                        map_bounds(bounds)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 绕 pivot 旋转，矩形、椭圆、文字、气泡移动中心并累加 rotation，其他图形直接旋转坐标
+pub fn rotate(shape: &mut Shape, pivot: Point, degrees: f32, fonts: &FontSet) {
+    let turn = |p: &mut Point| *p = rotate_point(*p, pivot, degrees);
+
+    if shape.kind.is_rotatable() {
+        let center = local_bounds(shape, fonts).center();
+        let moved = rotate_point(center, pivot, degrees);
+        shape.kind.translate(moved.x - center.x, moved.y - center.y);
+        shape.rotation = normalize_angle(shape.rotation + degrees);
+        return;
+    }
+
+    match &mut shape.kind {
+        ShapeKind::Line { from, to } => {
+            turn(from);
+            turn(to);
+        }
+        ShapeKind::Arrow {
+            from, to, control, ..
+        } => {
+            turn(from);
+            turn(to);
+            control.iter_mut().for_each(turn);
+        }
+        ShapeKind::Freehand { points } | ShapeKind::Highlighter { points } => {
+            points.iter_mut().for_each(turn)
+        }
+        ShapeKind::Counter { center, .. } => turn(center),
+        // 区域本身不旋转，只移动中心
+        ShapeKind::Spotlight { areas, .. } => {
+            for area in areas {
+                let (SpotlightArea::Rectangle(bounds) | SpotlightArea::Ellipse(bounds)) = area;
+                let center = bounds.center();
+                let moved = rotate_point(center, pivot, degrees);
+                bounds.x += moved.x - center.x;
+                bounds.y += moved.y - center.y;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 把手柄拖到 to：缩放手柄改变本地包围盒并保持对边在画布上不动，端点手柄直接移动端点
+pub fn drag_handle(shape: &mut Shape, handle: Handle, to: Point, fonts: &FontSet) {
+    let (pivot, rotation) = frame(shape, fonts);
+    let local = rotate_point(to, pivot, -rotation);
+
+    match (&mut shape.kind, handle) {
+        (ShapeKind::Line { from, .. }, Handle::Start)
+        | (ShapeKind::Arrow { from, .. }, Handle::Start) => *from = to,
+        (ShapeKind::Line { to: end, .. }, Handle::End)
+        | (ShapeKind::Arrow { to: end, .. }, Handle::End) => *end = to,
+        (ShapeKind::Arrow { control, .. }, Handle::Control) => *control = Some(to),
+        (ShapeKind::Callout { target, .. }, Handle::Target) => *target = local,
+        (kind, Handle::Rotate) if kind.is_rotatable() => {
+            shape.rotation = angle_from(local_bounds(shape, fonts).center(), to);
+        }
+        (_, handle) => {
+            let Some((ax, ay)) = handle.anchor() else {
+                return;
+            };
+            let before = local_bounds(shape, fonts);
+            let after = drag_box_handle(before, handle, local, None);
+
+            // 文字拖左右边改变换行宽度，其余手柄按比例缩放
+            match &mut shape.kind {
+                ShapeKind::Text {
+                    position,
+                    size,
+                    max_width,
+                    ..
+                } if ay == 0.5 => {
+                    // 背景框左右各有 size / 4 的内边距，见 text_style
+                    let padding = if shape.style.fill.is_some() {
+                        *size / 2.0
+                    } else {
+                        0.0
+                    };
+                    *max_width = Some((after.width - padding).max(1.0));
+                    position.x += after.x - before.x;
+                }
+                _ => scale(shape, before, after),
+            }
+
+            // 旋转中心随包围盒移动，平移回去让对边（对角）在画布上保持不动
+            if rotation != 0.0 {
+                let opposite = (1.0 - ax, 1.0 - ay);
+                let anchor = rotate_point(at(before, opposite), pivot, rotation);
+                let after = local_bounds(shape, fonts);
+                let moved = rotate_point(at(after, opposite), after.center(), rotation);
+                shape.kind.translate(anchor.x - moved.x, anchor.y - moved.y);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::image::{Color, Image};
+    use crate::editor::annotation::Style;
+
+    fn document() -> AnnotationDocument {
+        AnnotationDocument::new(Image::new(100, 100, vec![255; 100 * 100 * 4]))
+    }
+
+    #[test]
+    fn hit_test_respects_stroke_fill_and_order() {
+        let mut document = document();
+        let fonts = FontSet::bundled();
+        let outline = document.add(
+            ShapeKind::Rectangle {
+                bounds: Bounds::new(10.0, 10.0, 40.0, 40.0),
+            },
+            Style::default(),
+        );
+        let filled = document.add(
+            ShapeKind::Ellipse {
+                bounds: Bounds::new(60.0, 60.0, 20.0, 20.0),
+            },
+            Style {
+                fill: Some(Color::rgb(0, 0, 255)),
+                ..Style::default()
+            },
+        );
+
+        // 空心矩形只有边框可以点中
+        assert_eq!(
+            pick(&document, Point::new(11.0, 30.0), 2.0, &fonts),
+            Some(outline)
+        );
+        assert_eq!(pick(&document, Point::new(30.0, 30.0), 2.0, &fonts), None);
+        assert_eq!(
+            pick(&document, Point::new(70.0, 70.0), 2.0, &fonts),
+            Some(filled)
+        );
+        assert_eq!(
+            marquee(&document, Bounds::new(55.0, 55.0, 40.0, 40.0), &fonts),
+            vec![filled]
+        );
+    }
+
+    #[test]
+    fn resize_rotated_shape_keeps_opposite_corner() {
+        let fonts = FontSet::bundled();
+        let mut shape = Shape {
+            id: ShapeId(1),
+            kind: ShapeKind::Rectangle {
+                bounds: Bounds::new(0.0, 0.0, 20.0, 10.0),
+            },
+            style: Style::default(),
+            rotation: 90.0,
+        };
+
+        let handles = handles(&shape, &fonts);
+        let top_left = handles[0].1;
+        let bottom_right = handles[4].1;
+        // 旋转 90 度后左上角在右上方
+        assert!((top_left.x - 15.0).abs() < 1e-3 && (top_left.y + 5.0).abs() < 1e-3);
+
+        let to = Point::new(bottom_right.x - 5.0, bottom_right.y + 10.0);
+        drag_handle(&mut shape, Handle::BottomRight, to, &fonts);
+        let after = super::handles(&shape, &fonts);
+        assert!(distance(after[0].1, top_left) < 1e-3);
+        assert!(distance(after[4].1, to) < 1e-3);
+    }
+
+    #[test]
+    fn box_handle_with_aspect_ratio() {
+        let bounds = Bounds::new(0.0, 0.0, 40.0, 20.0);
+        let dragged = drag_box_handle(
+            bounds,
+            Handle::BottomRight,
+            Point::new(80.0, 25.0),
+            Some(2.0),
+        );
+        assert_eq!(dragged, Bounds::new(0.0, 0.0, 80.0, 40.0));
+
+        // 拖过对边时翻转
+        let flipped = drag_box_handle(bounds, Handle::Left, Point::new(50.0, 0.0), None);
+        assert_eq!(flipped, Bounds::new(40.0, 0.0, 10.0, 20.0));
+        assert_eq!(
+            angle_from(Point::new(0.0, 0.0), Point::new(10.0, 0.0)),
+            90.0
+        );
+    }
+}
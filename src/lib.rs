@@ -1,6 +1,7 @@
 mod util;
 mod ffi;
 mod core;
+mod editor;
 
 fn run_screenshot() {
     core::run();
@@ -1 +1,2 @@
+#[cfg(feature = "gui")]
 pub mod svg;
\ No newline at end of file
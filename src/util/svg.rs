@@ -1,39 +1,54 @@
 use std::fs;
 use std::io::Cursor;
 use std::ops::Deref;
+use std::rc::Rc;
 use std::time::Instant;
 use glium::{Display, Texture2d};
 use glium::texture::{RawImage2d, Texture2dDataSource};
+use glium::uniforms::SamplerBehavior;
 use imgui_glium_renderer::{Renderer, Texture};
-use screenshots::{Image, Screen};
-
-
-// pub fn load_svg_icon(renderer: &mut Renderer, svg_path: &str) -> Result<imgui::TextureId, String> {
-//     // 读取SVG文件
-//     let svg_data = std::fs::read(svg_path).unwrap();
-//     let rtree = usvg::Tree::from_data(&svg_data, &usvg::Options::default()).unwrap();
-//
-//     // 创建渲染目标
-//     let mut pixmap = tiny_skia::Pixmap::new(512, 512).unwrap();
-//     pixmap.fill(tiny_skia::Color::TRANSPARENT);
-//
-//     // 设置渲染参数
-//     let mut options = usvg::FitToOptions {
-//         padding: usvg::FitToPadding::Default,
-//         alignment: usvg::FitToAlignment::Center,
-//         ..Default::default()
-//     };
-//
-//     // 渲染SVG到渲染目标
-//     let mut rast = usvg::Rasterizer::new();
-//     usvg::NodeExt::render_to(&rtree.root(), tiny_skia::PixmapMut::from(&mut pixmap), &options).unwrap();
-//
-//     // 转换渲染目标为OpenGL纹理
-//     let texture = tiny_skia::rescale_surface(&pixmap, 512, 512);
-//     let texture_id = renderer.textures().insert(texture);
-//
-//     Ok(texture_id)
-// }
+use screenshots::Screen;
+
+use crate::core::image::{Color, Image, SvgRaster};
+
+
+/// 加载 SVG 图标并注册为 imgui 纹理
+///
+/// `size` 为图标的逻辑尺寸，`scale` 为当前屏幕的缩放比例，HiDPI 屏幕上按实际像素栅格化，图标不会发虚。
+/// `color` 会替换 SVG 中的 `currentColor`，换主题时用新的颜色重新加载即可。
+pub fn load_svg_icon(
+    renderer: &mut Renderer,
+    display: &Display,
+    svg_path: &str,
+    size: u32,
+    scale: f32,
+    color: Option<Color>,
+) -> Result<imgui::TextureId, String> {
+    // 读取SVG文件
+    let svg_data = fs::read(svg_path).map_err(|e| format!("{svg_path}: {e}"))?;
+
+    // 按缩放比例栅格化
+    let raster = SvgRaster {
+        size: Some((size, size)),
+        scale,
+        color,
+    };
+    let image = Image::from_svg(&svg_data, &raster).map_err(|e| format!("{svg_path}: {e}"))?;
+
+    // 转换为OpenGL纹理
+    let texture = Texture {
+        texture: Rc::new(image_to_2d(&image, display)?),
+        sampler: SamplerBehavior::default(),
+    };
+
+    Ok(renderer.textures().insert(texture))
+}
+
+/// 把 `Image` 上传为纹理，`Image` 的行已经是从上到下排列的，不需要反转
+pub fn image_to_2d(image: &Image, display: &Display) -> Result<Texture2d, String> {
+    let raw = RawImage2d::from_raw_rgba(image.rgba().clone(), (image.width(), image.height()));
+    Texture2d::new(display, raw).map_err(|e| e.to_string())
+}
 
 
 /// Load data to Texture2d